@@ -46,7 +46,7 @@ fn main() -> ! {
         info!("recv: cnt: {} {}", Debug2Format(&cnt), rx_buf[0..cnt]);
         // tx.write_bytes_blocking(&rx_buf);
 
-        let cnt = rx.read_idle_blocking(&mut rx_buf);
+        let cnt = rx.read_idle_blocking(&mut rx_buf).unwrap();
         info!("recv idle: cnt: {} {:x}", cnt, rx_buf[0..cnt]);
 
         // // 使用标准接口来发送串口数据
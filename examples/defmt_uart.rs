@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use py32f030_hal::{
+    self as hal,
+    mcu::peripherals::USART1,
+    mode::Blocking,
+    usart::{defmt_logger, AnyUsart, UsartTx},
+};
+use static_cell::StaticCell;
+
+use {defmt::info, panic_probe as _};
+
+static TX: StaticCell<UsartTx<'static, USART1, Blocking>> = StaticCell::new();
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    let p = hal::init(Default::default());
+    let gpioa = p.GPIOA.split();
+
+    let tx = gpioa.PA9;
+    let rx = gpioa.PA10;
+
+    let usart = AnyUsart::new(p.USART1, Some(rx), Some(tx), None, None, Default::default());
+    let (_, tx) = usart.split();
+
+    defmt_logger::init(TX.init(tx));
+
+    let mut cnt = 0;
+    loop {
+        info!("hello world {} {}", 123, cnt);
+        cnt += 1;
+        cortex_m::asm::delay(1000 * 1000 * 10);
+    }
+}
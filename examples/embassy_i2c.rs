@@ -8,6 +8,7 @@ use py32f030_hal::{self as hal, prelude::*};
 
 use embassy_executor::Spawner;
 use embassy_time::Timer;
+use hal::dma::AnyDma;
 use hal::i2c::{AnyI2c, Config};
 
 // use panic_halt as _;
@@ -36,8 +37,12 @@ async fn main(_spawner: Spawner) {
     let sda = gpioa.PA2;
     let scl = gpioa.PA3;
     let config = Config::default().speed(200_000);
+    // 主机收发使用 dma
+    let mut dma: AnyDma<_, Async> = AnyDma::new(p.DMA);
+    let [channel1, channel2, _] = dma.split();
     // 配置 200K的速度
-    let i2c1 = AnyI2c::<_, Async>::new(p.I2C, scl, sda, config).unwrap();
+    let i2c1 =
+        AnyI2c::<_, Async>::new(p.I2C, scl, sda, Some(channel1), Some(channel2), config).unwrap();
     let mut master = i2c1.as_master();
 
     _spawner.spawn(run()).unwrap();
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use defmt::Debug2Format;
+use py32f030_hal::mode::Async;
+use py32f030_hal::{self as hal};
+
+use embassy_executor::Spawner;
+use hal::i2c::{AnyI2c, Command, Config, SlaveConfig};
+
+use {defmt_rtt as _, panic_probe as _};
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    defmt::info!("i2c slave start...");
+    let p = hal::init(Default::default());
+
+    let gpioa = p.GPIOA.split();
+
+    let sda = gpioa.PA2;
+    let scl = gpioa.PA3;
+    let i2c1 = AnyI2c::<_, Async>::new(p.I2C, scl, sda, None, None, Config::default()).unwrap();
+
+    const SLAVE_DEVICE_ADDRESS: u8 = 0x3c;
+    let mut slave = i2c1.as_slave(SlaveConfig::new(SLAVE_DEVICE_ADDRESS));
+
+    let mut buf: [u8; 16] = [0; 16];
+    let mut len = 0;
+    loop {
+        match slave.listen().await {
+            Ok(Command::Write) | Ok(Command::GeneralCall) => {
+                let rst = slave.read_write(&mut buf).await;
+                defmt::info!("read rst: {:?} ", Debug2Format(&rst));
+                len = rst.unwrap_or(0);
+            }
+            Ok(Command::Read) => {
+                // 把最后一次收到的数据发回给主机
+                let rst = slave.respond_to_read(&buf[..len]).await;
+                defmt::info!("respond rst: {:?} ", Debug2Format(&rst));
+            }
+            Err(e) => defmt::info!("listen err: {:?} ", Debug2Format(&e)),
+        }
+    }
+}
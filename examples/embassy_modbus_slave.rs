@@ -0,0 +1,57 @@
+//! Modbus RTU 从机，通过 RS-485 收发器提供 8 个保持寄存器
+//!
+
+#![no_std]
+#![no_main]
+
+use embassy_executor::Spawner;
+use py32f030_hal::{
+    self as hal,
+    gpio::{Output, PinIoType, Speed},
+    modbus::{Exception, Handler, ModbusSlave},
+    usart::AnyUsart,
+};
+use {defmt_rtt as _, panic_probe as _};
+
+struct Registers([u16; 8]);
+
+impl Handler for Registers {
+    fn read_holding_register(&mut self, addr: u16) -> Result<u16, Exception> {
+        self.0
+            .get(addr as usize)
+            .copied()
+            .ok_or(Exception::IllegalDataAddress)
+    }
+
+    fn write_register(&mut self, addr: u16, value: u16) -> Result<(), Exception> {
+        let v = self
+            .0
+            .get_mut(addr as usize)
+            .ok_or(Exception::IllegalDataAddress)?;
+        defmt::info!("write {}: {}", addr, value);
+        *v = value;
+        Ok(())
+    }
+}
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = hal::init(Default::default());
+
+    let gpioa = p.GPIOA.split();
+    let de = Output::new(gpioa.PA1, PinIoType::PullDown, Speed::High);
+
+    let usart = AnyUsart::new_rs485(
+        p.USART1,
+        Some(gpioa.PA10),
+        gpioa.PA9,
+        de,
+        None,
+        None,
+        Default::default(),
+        Default::default(),
+    );
+
+    let mut slave = ModbusSlave::new(usart, 1);
+    slave.run(&mut Registers([0; 8])).await
+}
@@ -0,0 +1,48 @@
+//! 串口命令行，输入 help 查看所有命令
+//!
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use embassy_executor::Spawner;
+use py32f030_hal::{
+    self as hal,
+    shell::{Command, CommandError, Output, Shell},
+    usart::AnyUsart,
+};
+use {defmt_rtt as _, panic_probe as _};
+
+fn echo(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+    for (i, arg) in args.iter().enumerate() {
+        let _ = writeln!(out, "{}: {}", i, arg);
+    }
+    Ok(())
+}
+
+static COMMANDS: [Command; 1] = [Command {
+    name: "echo",
+    usage: "[args...]",
+    help: "逐行显示参数",
+    handler: echo,
+}];
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = hal::init(Default::default());
+
+    let gpioa = p.GPIOA.split();
+    let usart = AnyUsart::new(
+        p.USART1,
+        Some(gpioa.PA10),
+        Some(gpioa.PA9),
+        None,
+        None,
+        Default::default(),
+    );
+    let (mut rx, mut tx) = usart.split();
+
+    let mut shell = Shell::<64, 256, 256>::new(&COMMANDS, "py32> ");
+    let rst = shell.run(&mut rx, &mut tx).await;
+    defmt::error!("shell exit: {:?}", defmt::Debug2Format(&rst));
+}
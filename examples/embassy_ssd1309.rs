@@ -46,7 +46,7 @@ async fn main(_spawner: Spawner) {
     let config = Config::default().speed(200_000);
 
     // 配置 200K的速度
-    let i2c1 = AnyI2c::<_, Blocking>::new(p.I2C, scl, sda, config).unwrap();
+    let i2c1 = AnyI2c::<_, Blocking>::new(p.I2C, scl, sda, None, None, config).unwrap();
     let master = i2c1.as_master();
 
     let timer = AnyTimer::<_, Blocking>::new(p.TIM1).unwrap();
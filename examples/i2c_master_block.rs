@@ -27,8 +27,8 @@ fn main() -> ! {
     let scl = gpioa.PA3;
     let config = Config::default().speed(200_000);
     // 配置 200K的速度
-    let i2c1 = AnyI2c::<_, Blocking>::new(p.I2C, scl, sda, config).unwrap();
-    let master = i2c1.as_master();
+    let i2c1 = AnyI2c::<_, Blocking>::new(p.I2C, scl, sda, None, None, config).unwrap();
+    let mut master = i2c1.as_master();
 
     let buf: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 0];
     let mut r_buf: [u8; 2] = [0; 2];
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+use defmt::Debug2Format;
+use hal::i2c::{AnyI2c, Command, Config, SlaveConfig};
+use py32f030_hal::{self as hal, mode::Blocking};
+
+use {defmt_rtt as _, panic_probe as _};
+
+#[cortex_m_rt::entry]
+fn main() -> ! {
+    defmt::info!("i2c slave start...");
+    let p = hal::init(Default::default());
+
+    let gpioa = p.GPIOA.split();
+
+    let sda = gpioa.PA2;
+    let scl = gpioa.PA3;
+    let i2c1 = AnyI2c::<_, Blocking>::new(p.I2C, scl, sda, None, None, Config::default()).unwrap();
+
+    const SLAVE_DEVICE_ADDRESS: u8 = 0x3c;
+    let mut slave = i2c1.as_slave(SlaveConfig::new(SLAVE_DEVICE_ADDRESS).general_call(true));
+
+    let mut buf: [u8; 16] = [0; 16];
+    let mut len = 0;
+    loop {
+        match slave.listen_block() {
+            Ok(Command::Write) | Ok(Command::GeneralCall) => {
+                let rst = slave.read_block(&mut buf);
+                defmt::info!("read rst: {:?} ", Debug2Format(&rst));
+                len = rst.unwrap_or(0);
+            }
+            Ok(Command::Read) => {
+                // 把最后一次收到的数据发回给主机
+                let rst = slave.write_block(&buf[..len]);
+                defmt::info!("write rst: {:?} ", Debug2Format(&rst));
+            }
+            Err(e) => defmt::info!("listen err: {:?} ", Debug2Format(&e)),
+        }
+    }
+}
@@ -36,6 +36,24 @@ impl<'d, T: Instance> Crc<'d, T> {
         self.accumulat(buf)
     }
 
+    /// 计算字节数组的crc值
+    ///
+    /// 每 4 个字节按小端组成一个字写入，最后不足 4 个字节的部分高位补 0
+    pub fn calculate_bytes(&self, buf: &[u8]) -> u32 {
+        T::reset();
+        let mut chunks = buf.chunks_exact(4);
+        for v in &mut chunks {
+            T::write_data(u32::from_le_bytes([v[0], v[1], v[2], v[3]]));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0; 4];
+            word[..rest.len()].copy_from_slice(rest);
+            T::write_data(u32::from_le_bytes(word));
+        }
+        T::read_data()
+    }
+
     /// 复位结果
     pub fn reset(&self) {
         T::reset()
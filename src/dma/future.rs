@@ -1,7 +1,11 @@
 use super::{Channel, Event, Instance};
 use crate::pac::interrupt;
 use crate::{clock::peripheral::PeripheralInterrupt, mcu::peripherals::DMA};
-use core::{future::Future, marker::PhantomData, task::Poll};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    task::{Poll, Waker},
+};
 use critical_section::CriticalSection;
 
 #[cfg(feature = "embassy")]
@@ -80,18 +84,23 @@ impl<T: Instance> Future for EventFuture<T> {
             return Poll::Ready(events);
         }
 
-        EVENT_WAKERS[self.channel as usize].register(cx.waker());
-        // 开启中断标志
-        events
-            .iter()
-            .for_each(|event| T::event_config(self.channel, event, true));
-        // 开启通道的中断
-        self.channel.enable_interrupt();
+        register_waker::<T>(self.channel, cx.waker(), self.events);
         // 没有任何事件
         Poll::Pending
     }
 }
 
+/// 注册通道的唤醒器，并开启关注事件的中断
+pub(super) fn register_waker<T: Instance>(channel: Channel, waker: &Waker, events: EnumSet<Event>) {
+    EVENT_WAKERS[channel as usize].register(waker);
+    // 开启中断标志
+    events
+        .iter()
+        .for_each(|event| T::event_config(channel, event, true));
+    // 开启通道的中断
+    channel.enable_interrupt();
+}
+
 #[interrupt]
 fn DMA_CHANNEL1() {
     critical_section::with(|cs| unsafe {
@@ -104,6 +113,6 @@ fn DMA_CHANNEL2_3() {
     // 通道 2 和 通道 3可能会混，所以都遍历一遍
     critical_section::with(|cs| unsafe {
         EventFuture::<DMA>::on_interrupt(cs, Channel::Channel2, EnumSet::all());
-        EventFuture::<DMA>::on_interrupt(cs, Channel::Channel2, EnumSet::all())
+        EventFuture::<DMA>::on_interrupt(cs, Channel::Channel3, EnumSet::all())
     })
 }
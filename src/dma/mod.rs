@@ -12,6 +12,8 @@ use crate::mode::Async;
 use crate::mode::{Blocking, Mode};
 use crate::syscfg::{syscfg, DmaChannelMap};
 use core::marker::PhantomData;
+#[cfg(feature = "embassy")]
+use core::task::Waker;
 use embassy_hal_internal::{into_ref, Peripheral};
 use enumset::EnumSet;
 #[cfg(feature = "embassy")]
@@ -247,14 +249,17 @@ impl<'d, T: Instance, M: Mode> DmaChannel<'d, T, M> {
     pub fn remain(&self) -> u16 {
         T::remain_count(self.channel)
     }
-}
 
-impl<'d, T: Instance> DmaChannel<'d, T, Blocking> {
     /// 返回是否结束
     pub fn is_finish(&self) -> bool {
         T::event_flag(self.channel, Event::TCIF)
     }
 
+    /// 返回是否传输过半
+    pub fn is_half_finish(&self) -> bool {
+        T::event_flag(self.channel, Event::HTIF)
+    }
+
     /// 返回是否发生错误
     pub fn is_error(&self) -> bool {
         T::event_flag(self.channel, Event::TEIF)
@@ -266,7 +271,9 @@ impl<'d, T: Instance> DmaChannel<'d, T, Blocking> {
             T::event_clear(self.channel, e);
         }
     }
+}
 
+impl<'d, T: Instance> DmaChannel<'d, T, Blocking> {
     /// 等待传输完成
     pub fn wait_complet(&self) -> Result<(), Error> {
         while !T::event_flag(self.channel, Event::TCIF) {
@@ -313,4 +320,18 @@ impl<'d, T: Instance> DmaChannel<'d, T, Async> {
 
         Ok(())
     }
+
+    /// 注册唤醒器并开启事件中断，不清除事件标志
+    ///
+    /// 供需要自行处理标志的驱动（如循环模式接收）使用
+    pub(crate) fn register_waker(&self, waker: &Waker, events: EnumSet<Event>) {
+        future::register_waker::<T>(self.channel, waker, events)
+    }
+
+    /// 关闭事件中断
+    pub(crate) fn disable_event(&self, events: EnumSet<Event>) {
+        events
+            .iter()
+            .for_each(|event| T::event_config(self.channel, event, false));
+    }
 }
@@ -6,7 +6,7 @@ use core::marker::PhantomData;
 use drop_move::DropGuard;
 use embassy_hal_internal::{into_ref, Peripheral};
 
-use types::*;
+pub use types::*;
 
 pub const FLASH_PAGE_SIZE: usize = 128;
 pub const FLASH_PAGE_PER_SECTOR_CNT: usize = FLASH_PAGE_SIZE / 4;
@@ -0,0 +1,194 @@
+//! COBS（Consistent Overhead Byte Stuffing），帧以 0x00 结尾，帧内不会出现 0x00
+
+use super::{Codec, CodecError};
+
+pub struct Cobs;
+
+impl Codec for Cobs {
+    const DELIMITER: u8 = 0;
+
+    fn max_encoded_len(len: usize) -> usize {
+        // 每 254 个字节增加一个编码字节，再加上开头的编码字节和结尾的分隔符
+        len + len / 254 + 2
+    }
+
+    fn encode<I: IntoIterator<Item = u8>>(src: I, dst: &mut [u8]) -> Result<usize, CodecError> {
+        let mut put = |pos: usize, v: u8| match dst.get_mut(pos) {
+            Some(d) => {
+                *d = v;
+                Ok(())
+            }
+            None => Err(CodecError::Overflow),
+        };
+
+        // code 为当前块的编码字节，等于到下一个 0 的距离
+        let mut code_pos = 0;
+        let mut code = 1u8;
+        let mut pos = 1;
+        for v in src {
+            // 块已满，后面还有数据时才开始新的块，最后一个满块之后不需要额外的编码字节
+            if code == 0xff {
+                put(code_pos, code)?;
+                code_pos = pos;
+                code = 1;
+                pos += 1;
+            }
+            if v == 0 {
+                put(code_pos, code)?;
+                code_pos = pos;
+                code = 1;
+                pos += 1;
+            } else {
+                put(pos, v)?;
+                pos += 1;
+                code += 1;
+            }
+        }
+        put(code_pos, code)?;
+        put(pos, Self::DELIMITER)?;
+        Ok(pos + 1)
+    }
+
+    fn decode_in_place(buf: &mut [u8]) -> Result<usize, CodecError> {
+        let len = buf.len();
+        let mut read = 0;
+        let mut write = 0;
+        while read < len {
+            let code = buf[read];
+            if code == 0 {
+                return Err(CodecError::Corrupt);
+            }
+            read += 1;
+
+            let end = read + code as usize - 1;
+            if end > len || buf[read..end].contains(&0) {
+                return Err(CodecError::Corrupt);
+            }
+            buf.copy_within(read..end, write);
+            write += end - read;
+            read = end;
+
+            // 0xff 的块之后没有 0，最后一个块之后也没有 0
+            if code != 0xff && read < len {
+                buf[write] = 0;
+                write += 1;
+            }
+        }
+        Ok(write)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(src: &[u8], dst: &mut [u8]) -> usize {
+        let len = Cobs::encode(src.iter().copied(), dst).unwrap();
+        assert!(len <= Cobs::max_encoded_len(src.len()));
+        assert!(!dst[..len - 1].contains(&0));
+        assert_eq!(dst[len - 1], 0);
+        len
+    }
+
+    fn round_trip(src: &[u8]) {
+        let mut buf = [0xaa; 600];
+        let len = encode(src, &mut buf);
+        let len = Cobs::decode_in_place(&mut buf[..len - 1]).unwrap();
+        assert_eq!(&buf[..len], src);
+    }
+
+    /// `len` 个不为 0 的字节
+    fn non_zero(len: usize) -> [u8; 300] {
+        let mut data = [0; 300];
+        for (i, v) in data[..len].iter_mut().enumerate() {
+            *v = (i % 255) as u8 + 1;
+        }
+        data
+    }
+
+    #[test]
+    fn vectors() {
+        let cases: [(&[u8], &[u8]); 6] = [
+            (&[], &[0x01, 0x00]),
+            (&[0x00], &[0x01, 0x01, 0x00]),
+            (&[0x00, 0x00], &[0x01, 0x01, 0x01, 0x00]),
+            (&[0x00, 0x11, 0x00], &[0x01, 0x02, 0x11, 0x01, 0x00]),
+            (
+                &[0x11, 0x22, 0x00, 0x33],
+                &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00],
+            ),
+            (
+                &[0x11, 0x00, 0x00, 0x00],
+                &[0x02, 0x11, 0x01, 0x01, 0x01, 0x00],
+            ),
+        ];
+        for (src, expected) in cases {
+            let mut buf = [0; 16];
+            let len = encode(src, &mut buf);
+            assert_eq!(&buf[..len], expected);
+            round_trip(src);
+        }
+    }
+
+    #[test]
+    fn long_runs() {
+        for len in [0, 1, 253, 254, 255, 256, 300] {
+            let data = non_zero(len);
+            round_trip(&data[..len]);
+
+            // 前后带 0
+            let mut with_zero = [0; 302];
+            with_zero[1..len + 1].copy_from_slice(&data[..len]);
+            round_trip(&with_zero[..len + 2]);
+        }
+    }
+
+    #[test]
+    fn full_block() {
+        let data = non_zero(256);
+        let mut buf = [0; 300];
+
+        // 254 个字节正好是一个满块，之后没有额外的编码字节
+        let len = encode(&data[..254], &mut buf);
+        assert_eq!(len, 256);
+        assert_eq!(buf[0], 0xff);
+        assert_eq!(&buf[1..255], &data[..254]);
+
+        // 255 个字节，第二个块只有一个字节
+        let len = encode(&data[..255], &mut buf);
+        assert_eq!(len, 258);
+        assert_eq!(buf[255..258], [0x02, data[254], 0x00]);
+
+        let len = encode(&data[..256], &mut buf);
+        assert_eq!(len, 259);
+        assert_eq!(buf[255], 0x03);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut buf = [0; 4];
+        assert_eq!(
+            Cobs::encode([1, 2, 3, 4].into_iter(), &mut buf),
+            Err(CodecError::Overflow)
+        );
+        assert_eq!(Cobs::encode([1, 2].into_iter(), &mut buf), Ok(4));
+    }
+
+    #[test]
+    fn corrupt() {
+        // 编码字节超出了帧的长度
+        assert_eq!(
+            Cobs::decode_in_place(&mut [0x05, 0x11, 0x22]),
+            Err(CodecError::Corrupt)
+        );
+        // 帧内出现 0
+        assert_eq!(
+            Cobs::decode_in_place(&mut [0x03, 0x11, 0x00]),
+            Err(CodecError::Corrupt)
+        );
+        assert_eq!(
+            Cobs::decode_in_place(&mut [0x02, 0x11, 0x00, 0x01]),
+            Err(CodecError::Corrupt)
+        );
+    }
+}
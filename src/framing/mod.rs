@@ -0,0 +1,402 @@
+//! 数据包分帧
+//!
+//! 在任意 `embedded_io::Read`/`Write` 或 `embedded_io_async::Read`/`Write`（例如
+//! [`UsartRx`](crate::usart::UsartRx)/[`UsartTx`](crate::usart::UsartTx)）上收发二进制数据包，
+//! 支持 [`Cobs`] 和 [`Slip`] 两种编码。编解码和校验都是纯 Rust 实现，可以在主机上测试。
+//!
+//! - 接收的数据在 [`FrameReader`] 的缓冲区中原地解码，返回的数据包直接引用缓冲区
+//! - 超过缓冲区长度的帧返回 [`FrameError::Overflow`]，之后丢弃数据直到下一个分隔符
+//! - 解码或校验失败的帧返回错误，下一次读取从下一帧开始，不需要额外的同步操作
+//!
+//! 可选的校验尾由 [`Checksum`] 计算，硬件 [`Crc`] 实现了这个 trait，
+//! 对端可以使用 [`SoftCrc`] 计算同样的结果。
+//!
+//! ```rust, ignore
+//! let crc = Crc::new(p.CRC);
+//! let mut writer = FrameWriter::<_, Cobs, _, 64>::with_checksum(tx, &crc);
+//! writer.write_frame_async(&[1, 2, 3]).await?;
+//!
+//! let mut reader = FrameReader::<_, Cobs, _, 64>::with_checksum(rx, &crc);
+//! let packet = reader.read_frame_async().await?;
+//! ```
+
+mod cobs;
+mod slip;
+
+pub use cobs::Cobs;
+pub use slip::Slip;
+
+use crate::crc::{self, Crc};
+use core::marker::PhantomData;
+
+/// 编解码错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// 目标缓冲区太小
+    Overflow,
+    /// 编码格式错误
+    Corrupt,
+}
+
+/// 收发数据包的错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameError<E> {
+    /// 底层读写错误
+    Io(E),
+    /// 帧超过了缓冲区的长度
+    Overflow,
+    /// 帧的编码格式错误
+    Corrupt,
+    /// 校验错误
+    Checksum,
+    /// 底层读取返回了 0，没有更多的数据
+    Eof,
+}
+
+impl<E> From<CodecError> for FrameError<E> {
+    fn from(value: CodecError) -> Self {
+        match value {
+            CodecError::Overflow => Self::Overflow,
+            CodecError::Corrupt => Self::Corrupt,
+        }
+    }
+}
+
+/// 编码方式
+pub trait Codec {
+    /// 帧分隔符，编码后的帧内不会出现
+    const DELIMITER: u8;
+
+    /// `len` 个字节编码后的最大长度，包括分隔符
+    fn max_encoded_len(len: usize) -> usize;
+
+    /// 编码 `src` 到 `dst`，包括结尾的分隔符，返回编码后的长度
+    fn encode<I: IntoIterator<Item = u8>>(src: I, dst: &mut [u8]) -> Result<usize, CodecError>;
+
+    /// 原地解码一帧，`buf` 不包括分隔符，返回解码后的长度
+    fn decode_in_place(buf: &mut [u8]) -> Result<usize, CodecError>;
+}
+
+/// 数据包的校验尾
+pub trait Checksum {
+    /// 校验尾的长度，最多 4 个字节
+    const LEN: usize;
+
+    /// 计算校验值，校验尾为校验值的小端字节的前 [`Self::LEN`] 个字节
+    fn checksum(&mut self, data: &[u8]) -> u32;
+}
+
+/// 不使用校验尾
+pub struct NoChecksum;
+
+impl Checksum for NoChecksum {
+    const LEN: usize = 0;
+
+    fn checksum(&mut self, _data: &[u8]) -> u32 {
+        0
+    }
+}
+
+/// 硬件 CRC32，见 [`Crc::calculate_bytes`]
+impl<'d, T: crc::Instance> Checksum for &Crc<'d, T> {
+    const LEN: usize = 4;
+
+    fn checksum(&mut self, data: &[u8]) -> u32 {
+        self.calculate_bytes(data)
+    }
+}
+
+/// 软件实现的 CRC32，与硬件 [`Crc::calculate_bytes`] 的结果相同
+///
+/// 多项式 0x04C11DB7，初值 0xFFFFFFFF，不反转，结果不取反，
+/// 每 4 个字节按小端组成一个字，最后不足 4 个字节的部分高位补 0
+pub struct SoftCrc;
+
+impl SoftCrc {
+    pub fn calculate(data: &[u8]) -> u32 {
+        data.chunks(4).fold(0xffff_ffff, |crc, v| {
+            let mut word = [0; 4];
+            word[..v.len()].copy_from_slice(v);
+            let mut crc = crc ^ u32::from_le_bytes(word);
+            for _ in 0..32 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04c1_1db7
+                } else {
+                    crc << 1
+                };
+            }
+            crc
+        })
+    }
+}
+
+impl Checksum for SoftCrc {
+    const LEN: usize = 4;
+
+    fn checksum(&mut self, data: &[u8]) -> u32 {
+        Self::calculate(data)
+    }
+}
+
+/// 数据包接收，`N` 为缓冲区的长度，需要容纳编码后的一整帧
+pub struct FrameReader<R, C: Codec, K: Checksum, const N: usize> {
+    inner: R,
+    checksum: K,
+    buf: [u8; N],
+    /// 缓冲区中有效数据的长度
+    end: usize,
+    /// 上一次返回的帧占用的长度，下一次读取时丢弃
+    consumed: usize,
+    /// 正在丢弃过长的帧
+    discard: bool,
+    _codec: PhantomData<C>,
+}
+
+impl<R, C: Codec, const N: usize> FrameReader<R, C, NoChecksum, N> {
+    pub fn new(inner: R) -> Self {
+        Self::with_checksum(inner, NoChecksum)
+    }
+}
+
+impl<R, C: Codec, K: Checksum, const N: usize> FrameReader<R, C, K, N> {
+    /// 新建带校验尾的接收对象
+    pub fn with_checksum(inner: R, checksum: K) -> Self {
+        assert!(N > 0 && K::LEN <= 4);
+        Self {
+            inner,
+            checksum,
+            buf: [0; N],
+            end: 0,
+            consumed: 0,
+            discard: false,
+            _codec: PhantomData,
+        }
+    }
+
+    /// 返回底层读取对象，缓冲区中的数据被丢弃
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// 在缓冲区中查找下一帧
+    ///
+    /// 找到完整的一帧时返回解码后的长度，需要更多数据时返回 `None`
+    fn next_frame<E>(&mut self) -> Result<Option<usize>, FrameError<E>> {
+        loop {
+            if self.consumed > 0 {
+                self.buf.copy_within(self.consumed..self.end, 0);
+                self.end -= self.consumed;
+                self.consumed = 0;
+            }
+
+            let Some(i) = self.buf[..self.end].iter().position(|v| *v == C::DELIMITER) else {
+                if self.discard {
+                    self.end = 0;
+                } else if self.end == N {
+                    self.end = 0;
+                    self.discard = true;
+                    return Err(FrameError::Overflow);
+                }
+                return Ok(None);
+            };
+
+            self.consumed = i + 1;
+            // 过长的帧到此结束，连续的分隔符之间的空帧被忽略
+            if core::mem::take(&mut self.discard) || i == 0 {
+                continue;
+            }
+
+            let len = C::decode_in_place(&mut self.buf[..i])?;
+            if len < K::LEN {
+                return Err(FrameError::Corrupt);
+            }
+
+            let len = len - K::LEN;
+            let checksum = self.checksum.checksum(&self.buf[..len]).to_le_bytes();
+            if self.buf[len..len + K::LEN] != checksum[..K::LEN] {
+                return Err(FrameError::Checksum);
+            }
+            return Ok(Some(len));
+        }
+    }
+}
+
+impl<R: embedded_io::Read, C: Codec, K: Checksum, const N: usize> FrameReader<R, C, K, N> {
+    /// 阻塞读取一个数据包，不包括校验尾
+    pub fn read_frame(&mut self) -> Result<&[u8], FrameError<R::Error>> {
+        loop {
+            if let Some(len) = self.next_frame()? {
+                return Ok(&self.buf[..len]);
+            }
+            match self.inner.read(&mut self.buf[self.end..]) {
+                Ok(0) => return Err(FrameError::Eof),
+                Ok(cnt) => self.end += cnt,
+                Err(e) => return Err(FrameError::Io(e)),
+            }
+        }
+    }
+}
+
+impl<R: embedded_io_async::Read, C: Codec, K: Checksum, const N: usize> FrameReader<R, C, K, N> {
+    /// 读取一个数据包，不包括校验尾
+    pub async fn read_frame_async(&mut self) -> Result<&[u8], FrameError<R::Error>> {
+        loop {
+            if let Some(len) = self.next_frame()? {
+                return Ok(&self.buf[..len]);
+            }
+            match self.inner.read(&mut self.buf[self.end..]).await {
+                Ok(0) => return Err(FrameError::Eof),
+                Ok(cnt) => self.end += cnt,
+                Err(e) => return Err(FrameError::Io(e)),
+            }
+        }
+    }
+}
+
+/// 数据包发送，`N` 为缓冲区的长度，需要容纳编码后的一整帧
+pub struct FrameWriter<W, C: Codec, K: Checksum, const N: usize> {
+    inner: W,
+    checksum: K,
+    buf: [u8; N],
+    _codec: PhantomData<C>,
+}
+
+impl<W, C: Codec, const N: usize> FrameWriter<W, C, NoChecksum, N> {
+    pub fn new(inner: W) -> Self {
+        Self::with_checksum(inner, NoChecksum)
+    }
+}
+
+impl<W, C: Codec, K: Checksum, const N: usize> FrameWriter<W, C, K, N> {
+    /// 新建带校验尾的发送对象
+    pub fn with_checksum(inner: W, checksum: K) -> Self {
+        assert!(K::LEN <= 4);
+        Self {
+            inner,
+            checksum,
+            buf: [0; N],
+            _codec: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// 编码数据包和校验尾，返回编码后的长度
+    fn encode(&mut self, data: &[u8]) -> Result<usize, CodecError> {
+        let checksum = self.checksum.checksum(data).to_le_bytes();
+        let src = data.iter().chain(&checksum[..K::LEN]).copied();
+        C::encode(src, &mut self.buf)
+    }
+}
+
+impl<W: embedded_io::Write, C: Codec, K: Checksum, const N: usize> FrameWriter<W, C, K, N> {
+    /// 阻塞发送一个数据包
+    pub fn write_frame(&mut self, data: &[u8]) -> Result<(), FrameError<W::Error>> {
+        let len = self.encode(data)?;
+        self.inner
+            .write_all(&self.buf[..len])
+            .map_err(FrameError::Io)
+    }
+}
+
+impl<W: embedded_io_async::Write, C: Codec, K: Checksum, const N: usize> FrameWriter<W, C, K, N> {
+    /// 发送一个数据包
+    pub async fn write_frame_async(&mut self, data: &[u8]) -> Result<(), FrameError<W::Error>> {
+        let len = self.encode(data)?;
+        self.inner
+            .write_all(&self.buf[..len])
+            .await
+            .map_err(FrameError::Io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用 `FrameWriter` 把数据包编码到 `out` 中，返回编码后的总长度
+    fn write_frames<C: Codec>(frames: &[&[u8]], out: &mut [u8]) -> usize {
+        let total = out.len();
+        let mut writer = FrameWriter::<_, C, _, 64>::with_checksum(out, SoftCrc);
+        for frame in frames {
+            writer.write_frame(frame).unwrap();
+        }
+        total - writer.into_inner().len()
+    }
+
+    #[test]
+    fn soft_crc() {
+        // 与 STM32 系列硬件 CRC 单元的参考值相同
+        assert_eq!(SoftCrc::calculate(&[0x78, 0x56, 0x34, 0x12]), 0xdf8a_8a2b);
+        assert_eq!(SoftCrc::calculate(&[]), 0xffff_ffff);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buf = [0; 128];
+        let len = write_frames::<Cobs>(&[&[1, 0, 2], &[], &[0xc0, 0xdb]], &mut buf);
+
+        let mut reader = FrameReader::<_, Cobs, _, 64>::with_checksum(&buf[..len], SoftCrc);
+        assert_eq!(reader.read_frame(), Ok(&[1, 0, 2][..]));
+        assert_eq!(reader.read_frame(), Ok(&[][..]));
+        assert_eq!(reader.read_frame(), Ok(&[0xc0, 0xdb][..]));
+        assert_eq!(reader.read_frame(), Err(FrameError::Eof));
+
+        let len = write_frames::<Slip>(&[&[1, 0, 2], &[0xc0, 0xdb]], &mut buf);
+        let mut reader = FrameReader::<_, Slip, _, 64>::with_checksum(&buf[..len], SoftCrc);
+        assert_eq!(reader.read_frame(), Ok(&[1, 0, 2][..]));
+        assert_eq!(reader.read_frame(), Ok(&[0xc0, 0xdb][..]));
+        assert_eq!(reader.read_frame(), Err(FrameError::Eof));
+    }
+
+    #[test]
+    fn bad_checksum() {
+        let mut buf = [0; 128];
+        let len = write_frames::<Slip>(&[&[1, 2, 3], &[4, 5]], &mut buf);
+        // 修改第一帧的校验尾，编码后的帧为 END 1 2 3 crc*4 END
+        buf[4] ^= 0x01;
+
+        let mut reader = FrameReader::<_, Slip, _, 64>::with_checksum(&buf[..len], SoftCrc);
+        assert_eq!(reader.read_frame(), Err(FrameError::Checksum));
+        assert_eq!(reader.read_frame(), Ok(&[4, 5][..]));
+
+        // 比校验尾还短的帧
+        let buf = [0x02, 0x11, 0x00];
+        let mut reader = FrameReader::<_, Cobs, _, 64>::with_checksum(&buf[..], SoftCrc);
+        assert_eq!(reader.read_frame(), Err(FrameError::Corrupt));
+    }
+
+    #[test]
+    fn resync_after_corrupt() {
+        // 第一帧的编码字节超出了帧的长度
+        let buf = [0x05, 0x11, 0x00, 0x03, 0x22, 0x33, 0x00];
+        let mut reader = FrameReader::<_, Cobs, _, 16>::new(&buf[..]);
+        assert_eq!(reader.read_frame(), Err(FrameError::Corrupt));
+        assert_eq!(reader.read_frame(), Ok(&[0x22, 0x33][..]));
+        assert_eq!(reader.read_frame(), Err(FrameError::Eof));
+    }
+
+    #[test]
+    fn resync_after_overflow() {
+        // 20 个字节的帧超过了 8 个字节的缓冲区
+        let mut buf = [0x11; 26];
+        buf[0] = 21;
+        buf[21] = 0x00;
+        buf[22..].copy_from_slice(&[0x03, 0x22, 0x33, 0x00]);
+
+        let mut reader = FrameReader::<_, Cobs, _, 8>::new(&buf[..]);
+        assert_eq!(reader.read_frame(), Err(FrameError::Overflow));
+        assert_eq!(reader.read_frame(), Ok(&[0x22, 0x33][..]));
+        assert_eq!(reader.read_frame(), Err(FrameError::Eof));
+    }
+
+    #[test]
+    fn writer_overflow() {
+        let mut buf = [0; 64];
+        let mut writer = FrameWriter::<_, Slip, _, 8>::new(&mut buf[..]);
+        assert_eq!(writer.write_frame(&[1; 6]), Ok(()));
+        assert_eq!(writer.write_frame(&[1; 7]), Err(FrameError::Overflow));
+    }
+}
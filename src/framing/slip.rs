@@ -0,0 +1,128 @@
+//! SLIP（RFC 1055），帧前后都有 END，帧内的 END 和 ESC 被转义
+
+use super::{Codec, CodecError};
+
+pub const END: u8 = 0xc0;
+pub const ESC: u8 = 0xdb;
+pub const ESC_END: u8 = 0xdc;
+pub const ESC_ESC: u8 = 0xdd;
+
+pub struct Slip;
+
+impl Codec for Slip {
+    const DELIMITER: u8 = END;
+
+    fn max_encoded_len(len: usize) -> usize {
+        len * 2 + 2
+    }
+
+    fn encode<I: IntoIterator<Item = u8>>(src: I, dst: &mut [u8]) -> Result<usize, CodecError> {
+        let mut pos = 0;
+        let mut put = |v: u8| match dst.get_mut(pos) {
+            Some(d) => {
+                *d = v;
+                pos += 1;
+                Ok(())
+            }
+            None => Err(CodecError::Overflow),
+        };
+
+        // 开头的 END 使接收方丢弃之前线路上的噪声
+        put(END)?;
+        for v in src {
+            match v {
+                END => {
+                    put(ESC)?;
+                    put(ESC_END)?;
+                }
+                ESC => {
+                    put(ESC)?;
+                    put(ESC_ESC)?;
+                }
+                v => put(v)?,
+            }
+        }
+        put(END)?;
+        Ok(pos)
+    }
+
+    fn decode_in_place(buf: &mut [u8]) -> Result<usize, CodecError> {
+        let len = buf.len();
+        let mut read = 0;
+        let mut write = 0;
+        while read < len {
+            let v = match buf[read] {
+                END => return Err(CodecError::Corrupt),
+                ESC => {
+                    read += 1;
+                    match buf.get(read) {
+                        Some(&ESC_END) => END,
+                        Some(&ESC_ESC) => ESC,
+                        _ => return Err(CodecError::Corrupt),
+                    }
+                }
+                v => v,
+            };
+            buf[write] = v;
+            write += 1;
+            read += 1;
+        }
+        Ok(write)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(src: &[u8], dst: &mut [u8]) -> usize {
+        let len = Slip::encode(src.iter().copied(), dst).unwrap();
+        assert!(len <= Slip::max_encoded_len(src.len()));
+        len
+    }
+
+    #[test]
+    fn escape() {
+        let src = [0x01, END, 0x02, ESC, ESC_END, ESC_ESC];
+        let mut buf = [0; 16];
+        let len = encode(&src, &mut buf);
+        assert_eq!(
+            buf[..len],
+            [END, 0x01, ESC, ESC_END, 0x02, ESC, ESC_ESC, ESC_END, ESC_ESC, END]
+        );
+
+        // 去掉前后的 END 后原地解码
+        let len = Slip::decode_in_place(&mut buf[1..len - 1]).unwrap();
+        assert_eq!(buf[1..len + 1], src);
+    }
+
+    #[test]
+    fn worst_case() {
+        let src = [END, ESC, END, ESC];
+        let mut buf = [0; 10];
+        assert_eq!(encode(&src, &mut buf), Slip::max_encoded_len(src.len()));
+
+        let mut buf = [0; 9];
+        assert_eq!(
+            Slip::encode(src.into_iter(), &mut buf),
+            Err(CodecError::Overflow)
+        );
+    }
+
+    #[test]
+    fn corrupt() {
+        // ESC 后面只能是 ESC_END 或 ESC_ESC
+        assert_eq!(
+            Slip::decode_in_place(&mut [0x01, ESC, 0x02]),
+            Err(CodecError::Corrupt)
+        );
+        assert_eq!(
+            Slip::decode_in_place(&mut [0x01, ESC]),
+            Err(CodecError::Corrupt)
+        );
+        assert_eq!(
+            Slip::decode_in_place(&mut [0x01, END]),
+            Err(CodecError::Corrupt)
+        );
+    }
+}
@@ -1,11 +1,18 @@
 use super::{Error, Event, Instance};
 use crate::{mcu::peripherals::I2C, pac::interrupt};
-use core::{future::Future, marker::PhantomData, task::Poll};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    task::{Poll, Waker},
+};
 use embassy_sync::waitqueue::AtomicWaker;
 use enumset::EnumSet;
 
 static WAKER: [AtomicWaker; 1] = [AtomicWaker::new(); 1];
 
+/// 等待任意一个事件，返回出现的事件，期间出现的总线错误、仲裁丢失、应答失败和过载都会返回错误
+///
+/// 应答失败返回 [`Error::Nack`]，由调用者区分地址和数据阶段
 pub struct EventFuture<T: Instance> {
     _t: PhantomData<T>,
     events: EnumSet<Event>,
@@ -13,7 +20,6 @@ pub struct EventFuture<T: Instance> {
 
 impl<T: Instance> EventFuture<T> {
     pub fn new(events: EnumSet<Event>) -> Self {
-        events.iter().for_each(|event| T::event_config(event, true));
         Self {
             _t: PhantomData,
             events,
@@ -23,39 +29,55 @@ impl<T: Instance> EventFuture<T> {
     /// 中断函数调用
     #[inline]
     unsafe fn on_interrupt() {
-        // 关闭已经发生的中断事件
-        EnumSet::all().iter().for_each(|event| {
-            /* 匹配到中断了 */
-            if T::is_event_match(event) {
-                // 清除标志
-                T::event_clear(event);
-                // 关闭该中断
-                T::event_config(event, false);
-            }
-        });
+        // 标志留给任务处理，这里只关闭中断，避免标志没有清除时反复进入中断
+        T::disable_interrupts();
         WAKER[T::id() as usize].wake()
     }
 }
 
 impl<T: Instance> Future for EventFuture<T> {
-    type Output = Result<(), Error>;
+    type Output = Result<Event, Error>;
     fn poll(
         self: core::pin::Pin<&mut Self>,
         cx: &mut core::task::Context<'_>,
     ) -> core::task::Poll<Self::Output> {
         WAKER[T::id() as usize].register(cx.waker());
 
+        T::check_error()?;
+
         for event in self.events {
             if T::event_flag(event) {
                 T::event_clear(event);
-                return Poll::Ready(Ok(()));
+                return Poll::Ready(Ok(event));
             }
         }
 
+        // 缓冲区中断需要同时开启事件中断，错误中断总是开启
+        self.events
+            .iter()
+            .for_each(|event| T::event_config(event, true));
+        T::event_config(Event::SB, true);
+        T::event_config(Event::BERR, true);
+
         Poll::Pending
     }
 }
 
+impl<T: Instance> Drop for EventFuture<T> {
+    fn drop(&mut self) {
+        T::disable_interrupts();
+    }
+}
+
+/// 注册唤醒器并开启事件和错误中断，不检查也不清除标志
+///
+/// 供同时等待 dma 的传输使用
+pub(super) fn register_waker<T: Instance>(waker: &Waker, events: EnumSet<Event>) {
+    WAKER[T::id() as usize].register(waker);
+    events.iter().for_each(|event| T::event_config(event, true));
+    T::event_config(Event::BERR, true);
+}
+
 #[interrupt]
 fn I2C1() {
     critical_section::with(|_cs| unsafe { EventFuture::<I2C>::on_interrupt() })
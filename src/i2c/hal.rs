@@ -1,7 +1,6 @@
 pub(super) mod sealed {
     use super::super::*;
     use crate::clock::sys_pclk;
-    use crate::delay::wait_for_true_timeout_block;
     use crate::i2c::Id;
     use crate::pac;
     pub const WAIT_FLAG_TIMEOUT: usize = 100_000;
@@ -155,6 +154,7 @@ pub(super) mod sealed {
                 }
                 Event::STOPF => {
                     // 软件读取 I2C_SR1 寄存器后，对 I2C_CR1 寄存器的写操作将清除该位，或当 PE=0 时，硬件清除该位。
+                    Self::block().cr1.modify(|_, w| w);
                     w
                 }
                 Event::BTF => {
@@ -180,156 +180,103 @@ pub(super) mod sealed {
             });
         }
 
-        /// ACK/PEC 位置（用于数据接收），软件可置位/清零该寄存器，或 PE=0 时由硬件清零。
-        ///
-        /// - 0：ACK 位控制当前移位寄存器内正在接收的字节的(N)ACK。PEC 位表明当前移位寄存器内的字节是 PEC
-        /// - 1：ACK 位控制在移位寄存器里接收的下一个字节的(N)ACK。PEC 位表明在移位寄存器里接收的下一个字节是 PEC
-        ///      注：POS 位只能用在 2 字节的接收配置中，必须在接收数据之前配置。为了 NACK 第 2 个字节，必须在清除 ADDR 之后清除 ACK 位。为了检测第 2 个字节的 PEC，必须在配置了POS 位之后，ADDR stretch 事件时设置 PEC位。
+        /// 读取接收到的数据
         #[inline]
-        fn clear_pos() {
-            Self::block().cr1.modify(|_, w| w.pos().clear_bit());
+        fn read() -> u8 {
+            Self::block().dr.read().dr().bits()
         }
 
-        fn master_transmit_block(address: u8, buf: &[u8]) -> Result<usize, Error> {
-            // 如果总线处于busy状态，则退出
-            // wait_for_flag_timeout(WAIT_FLAG_TIMEOUT_US, || Self::busy() == false)
-            //     .map_err(|_| Error::Busy)?;
-
-            Self::clear_pos();
-
-            Self::start();
-            // SB=1，通过读 SR1，再向 DR 寄存器写数据，实现对该位的清零
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::SB))
-                .map_err(|_| {
-                    Self::event_clear(Event::ARLO);
-                    Error::Start
-                })?;
-
-            Self::transmit(address << 1);
-
-            // ADDR=1，通过读 SR1，再读 SR2，实现对该位的清零
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::ADD))
-                .map_err(|_| {
-                    // Self::debug();
-                    // 清除 af 置位
-                    Self::event_clear(Event::AF);
-                    Self::stop();
-                    Error::Address
-                })?;
-            Self::event_clear(Event::ADD);
-
-            // TRA 位指示主设备是在接收器模式还是发送器模式。
-
-            let mut iter = buf.iter();
-            if let Some(d) = iter.next() {
-                // EV8_1：TxE=1, shift 寄存器 empty，数据寄存器 empty，向 DR 寄存器写 Data1
-                wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::TXE))
-                    .map_err(|_| Error::Tx)?;
-                Self::transmit(*d);
-            }
+        /// 停止信号还没有发出
+        #[inline]
+        fn stop_pending() -> bool {
+            Self::block().cr1.read().stop().bit()
+        }
 
-            // 接着将后面的数据发送出去
-            for t in iter {
-                // EV8：TxE=1, shift 寄存器不 empty，数据寄存器 empty，向 DR 寄存器写 Data2，该位被清零
-                wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::TXE))
-                    .map_err(|_| {
-                        Self::stop();
-                        Error::Tx
-                    })?;
-                Self::transmit(*t);
-            }
+        /// 关闭事件、缓冲区和错误中断
+        #[inline]
+        fn disable_interrupts() {
+            Self::block().cr2.modify(|_, w| {
+                w.itevten()
+                    .clear_bit()
+                    .itbufen()
+                    .clear_bit()
+                    .iterren()
+                    .clear_bit()
+            })
+        }
 
-            // EV8_2：TxE=1, BTF=1, 写 Stop 位寄存器，当硬件发出 Stop 位时，TxE 和 BTF 被清零
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::TXE))
-                .map_err(|_| {
-                    Self::stop();
-                    Error::Tx
-                })?;
+        /// 配置从机地址、广播呼叫和时钟延展，并开启地址应答
+        fn slave_config(address: u8, general_call: bool, clock_stretch: bool) {
+            Self::enable_config(false);
+            Self::address(address);
+            Self::block()
+                .cr1
+                .modify(|_, w| w.engc().bit(general_call).nostretch().bit(!clock_stretch));
+            Self::enable_config(true);
+            Self::ack(true);
+        }
 
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::TXE))
-                .map_err(|_| {
-                    Self::stop();
-                    Error::Tx
-                })?;
+        /// 开启或关闭 dma 请求
+        #[inline]
+        fn dma_enable(en: bool) {
+            Self::block().cr2.modify(|_, w| w.dmaen().bit(en));
+        }
 
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::BTF))
-                .map_err(|_| {
-                    Self::stop();
-                    Error::Tx
-                })?;
+        /// 主机接收时 dma 传输的最后一个字节回复 NACK
+        #[inline]
+        fn dma_last(en: bool) {
+            Self::block().cr2.modify(|_, w| w.last().bit(en));
+        }
 
-            Self::stop();
+        /// 数据寄存器的地址
+        #[inline]
+        fn dr_address() -> u32 {
+            Self::block().dr.as_ptr() as u32
+        }
 
-            Ok(buf.len())
+        /// 从机地址匹配后的传输方向，返回 (TRA, GENCALL)
+        ///
+        /// 清除 ADDR 之后仍然有效，直到停止或重复起始条件
+        #[inline]
+        fn slave_direction() -> (bool, bool) {
+            let sr2 = Self::block().sr2.read();
+            (sr2.tra().bit(), sr2.gencall().bit())
         }
 
-        fn master_receive_block(address: u8, buf: &mut [u8]) -> Result<usize, Error> {
-            let block = Self::block();
-            Self::clear_pos();
-
-            Self::start();
-            // EV5：SB=1, 先读 SR1 寄存器，再写 DR 寄存器，清零该位
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::SB))
-                .map_err(|_| Error::Start)?;
-
-            Self::transmit((address << 1) | 1);
-
-            // EV6：ADDR，先读 SR1，再读 SR2，清零该位
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || Self::event_flag(Event::ADD))
-                .map_err(|_| Error::Address)?;
-            Self::event_clear(Event::ADD);
-
-            let len = buf.len();
-
-            let mut enumerate = buf.iter_mut().enumerate();
-            while let Some((idx, p)) = enumerate.next() {
-                let remain = len - idx;
-                if remain > 2 {
-                    Self::ack(true);
-                    // EV7：RxNE=1, 读 DR 寄存器清零该位
-                    wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || {
-                        Self::event_flag(Event::RXNE)
-                    })
-                    .map_err(|_| Error::RX)?;
-
-                    // 读取数据
-                    *p = block.dr.read().dr().bits();
-
-                    if Self::event_flag(Event::BTF) {
-                        Self::ack(true);
-                        let (_, p) = enumerate.next().unwrap();
-                        *p = block.dr.read().dr().bits();
-                    }
-                } else if remain == 2 {
-                    Self::ack(false);
-
-                    // EV7：RxNE=1, 读 DR 寄存器清零该位
-                    wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || {
-                        Self::event_flag(Event::RXNE)
-                    })
-                    .map_err(|_| Error::RX)?;
-                    // 读取数据
-                    *p = block.dr.read().dr().bits();
-                    if Self::event_flag(Event::BTF) {
-                        Self::ack(false);
-                        let (_, p) = enumerate.next().unwrap();
-                        *p = block.dr.read().dr().bits();
-                    }
-                } else if remain == 1 {
-                    Self::ack(false);
-                    // EV7：RxNE=1, 读 DR 寄存器清零该位
-                    wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || {
-                        Self::event_flag(Event::RXNE)
-                    })
-                    .map_err(|_| Error::RX)?;
-                    // 读取数据
-                    *p = block.dr.read().dr().bits();
-                }
-            }
+        /// 检查并清除错误标志，应答失败返回 [`Error::Nack`]，由调用者区分地址和数据阶段
+        fn check_error() -> Result<(), Error> {
+            let sr1 = Self::block().sr1.read();
+            let error = if sr1.berr().bit() {
+                Self::event_clear(Event::BERR);
+                Error::Bus
+            } else if sr1.arlo().bit() {
+                Self::event_clear(Event::ARLO);
+                Error::ArbitrationLost
+            } else if sr1.af().bit() {
+                Self::event_clear(Event::AF);
+                Error::Nack
+            } else if sr1.ovr().bit() {
+                Self::event_clear(Event::OVR);
+                Error::Overrun
+            } else {
+                return Ok(());
+            };
+            Err(error)
+        }
 
-            Self::stop();
+        /// ACK/PEC 位置（用于数据接收），软件可置位/清零该寄存器，或 PE=0 时由硬件清零。
+        ///
+        /// - 0：ACK 位控制当前移位寄存器内正在接收的字节的(N)ACK。PEC 位表明当前移位寄存器内的字节是 PEC
+        /// - 1：ACK 位控制在移位寄存器里接收的下一个字节的(N)ACK。PEC 位表明在移位寄存器里接收的下一个字节是 PEC
+        ///      注：POS 位只能用在 2 字节的接收配置中，必须在接收数据之前配置。为了 NACK 第 2 个字节，必须在清除 ADDR 之后清除 ACK 位。为了检测第 2 个字节的 PEC，必须在配置了POS 位之后，ADDR stretch 事件时设置 PEC位。
+        #[inline]
+        fn clear_pos() {
+            Self::set_pos(false);
+        }
 
-            Ok(buf.len())
+        #[inline]
+        fn set_pos(en: bool) {
+            Self::block().cr1.modify(|_, w| w.pos().bit(en));
         }
 
         fn config(config: Config) -> Result<(), Error> {
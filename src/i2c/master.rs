@@ -1,31 +1,159 @@
-#[cfg(feature = "embassy")]
 use enumset::EnumSet;
 
 #[cfg(feature = "embassy")]
-use super::future::EventFuture;
+use super::future::{self, EventFuture};
 use super::hal::sealed::WAIT_FLAG_TIMEOUT;
 use super::{Error, Event, Instance};
 use crate::delay::wait_for_true_timeout_block;
+use crate::dma::{self, DmaChannel};
+use crate::mcu::peripherals::DMA;
 #[cfg(feature = "embassy")]
 use crate::mode::Async;
+use crate::syscfg::DmaChannelMap;
 use crate::{
     clock::peripheral::PeripheralInterrupt,
     mode::{Blocking, Mode},
 };
+use core::cell::Cell;
 use core::marker::PhantomData;
+#[cfg(feature = "embassy")]
+use core::{future::poll_fn, task::Poll};
+#[cfg(feature = "embassy")]
+use drop_move::DropGuard;
 use embedded_hal::i2c::Operation;
 
 /// Master 角色
+///
+/// 创建时提供了 dma 通道则使用 dma 收发：发送时每个写操作使用一次 dma 传输，
+/// 接收时不少于 2 个字节的单个读操作使用 dma，最后一个字节由硬件自动回复 NACK
 pub struct Master<'d, T: Instance, M: Mode> {
     _t: PhantomData<(&'d T, M)>,
+    rx_dma: Option<DmaChannel<'d, DMA, M>>,
+    tx_dma: Option<DmaChannel<'d, DMA, M>>,
 }
 
 impl<'d, T: Instance, M: Mode> Master<'d, T, M> {
-    pub(super) fn new() -> Self {
+    pub(super) fn new(
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+    ) -> Self {
         if M::is_async() {
             T::id().enable_interrupt();
         }
-        Self { _t: PhantomData }
+        Self {
+            _t: PhantomData,
+            rx_dma,
+            tx_dma,
+        }
+    }
+
+    /// 结束一组传输，最后一组发送停止条件，否则发送重复起始条件
+    fn end_group(last: bool) {
+        if last {
+            T::stop()
+        } else {
+            T::start()
+        }
+    }
+
+    /// 从 `start` 开始的相邻同方向操作的结束位置
+    fn group_end(operations: &[Operation<'_>], start: usize) -> usize {
+        let is_read = matches!(operations[start], Operation::Read(_));
+        let mut end = start + 1;
+        while end < operations.len() && matches!(operations[end], Operation::Read(_)) == is_read {
+            end += 1;
+        }
+        end
+    }
+
+    /// 一组操作的总字节数
+    fn group_len(group: &[Operation<'_>]) -> usize {
+        group
+            .iter()
+            .map(|op| match op {
+                Operation::Read(buf) => buf.len(),
+                Operation::Write(buf) => buf.len(),
+            })
+            .sum()
+    }
+
+    /// 主机接收时 ACK 只影响数据，可以在发送地址之前配置
+    fn read_config(len: usize, dma: bool) -> Result<(), Error> {
+        match len {
+            0 => return Err(Error::RX),
+            // dma 接收由 LAST 控制最后一个字节的 NACK
+            _ if dma => T::ack(true),
+            1 => T::ack(false),
+            2 => {
+                T::ack(true);
+                T::set_pos(true);
+            }
+            _ => T::ack(true),
+        }
+        Ok(())
+    }
+
+    /// 配置并开启 dma 发送
+    fn dma_write_start(dma: &mut DmaChannel<'d, DMA, M>, buf: &[u8]) {
+        dma.clear_flag(EnumSet::all());
+        dma.config(dma::Config::new_mem2periph(
+            buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            T::dr_address(),
+            false,
+            dma::Burst::Single,
+            dma::Priorities::Medium,
+            dma::RepeatMode::OneTime(buf.len() as u16),
+        ));
+        dma.bind(DmaChannelMap::I2C_TX);
+        dma.start();
+        T::dma_enable(true);
+    }
+
+    /// 配置并开启 dma 接收，`buf` 不少于 2 个字节
+    fn dma_read_start(dma: &mut DmaChannel<'d, DMA, M>, buf: &mut [u8]) {
+        dma.clear_flag(EnumSet::all());
+        dma.config(dma::Config::new_periph2mem(
+            T::dr_address(),
+            false,
+            dma::Burst::Single,
+            buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            dma::Priorities::Medium,
+            dma::RepeatMode::OneTime(buf.len() as u16),
+        ));
+        dma.bind(DmaChannelMap::I2C_RX);
+        dma.start();
+        T::dma_last(true);
+        T::dma_enable(true);
+    }
+
+    /// 关闭 dma 请求
+    fn dma_finish(dma: &mut DmaChannel<'d, DMA, M>) {
+        T::dma_enable(false);
+        T::dma_last(false);
+        dma.stop();
+        dma.clear_flag(EnumSet::all());
+    }
+
+    /// 传输结束后的清理，出错时释放总线，并等待停止条件发出
+    fn finish(rst: Result<(), Error>) -> Result<(), Error> {
+        T::dma_enable(false);
+        T::dma_last(false);
+        T::clear_pos();
+        match rst {
+            // 仲裁丢失后已经不是主机，不能发送停止条件
+            Err(Error::ArbitrationLost) => return rst,
+            Err(_) => T::stop(),
+            Ok(()) => {}
+        }
+
+        // 等待停止条件发出，之后才能开始下一次传输
+        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || !T::stop_pending())
+            .map_err(|_| Error::Stop)?;
+        rst
     }
 }
 
@@ -38,83 +166,241 @@ impl<'d, T: Instance, M: Mode> Drop for Master<'d, T, M> {
 }
 
 impl<'d, T: Instance> Master<'d, T, Blocking> {
-    pub fn write_block(&self, address: u8, buf: &[u8]) -> Result<usize, Error> {
+    pub fn write_block(&mut self, address: u8, buf: &[u8]) -> Result<usize, Error> {
+        self.transaction_block(address, &mut [Operation::Write(buf)])?;
+        Ok(buf.len())
+    }
+
+    pub fn read_block(&mut self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = buf.len();
+        self.transaction_block(address, &mut [Operation::Read(buf)])?;
+        Ok(len)
+    }
+
+    /// 写入后发送重复起始条件再读取，中间没有停止条件
+    pub fn write_read_block(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error> {
+        self.transaction_block(
+            address,
+            &mut [Operation::Write(write), Operation::Read(read)],
+        )
+    }
+
+    /// 执行一组操作
+    ///
+    /// 相邻的同方向操作合并为一次传输，方向改变时发送重复起始条件和地址，最后发送停止条件。
+    /// 不支持长度为 0 的读取
+    pub fn transaction_block(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        let rst = self.transfer_block(address, operations);
+        Self::finish(rst)
+    }
+
+    fn transfer_block(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
         T::clear_pos();
 
-        T::start();
+        let cnt = operations.len();
+        let mut start = 0;
+        while start < cnt {
+            let is_read = matches!(operations[start], Operation::Read(_));
+            let end = Self::group_end(operations, start);
+            let group = &mut operations[start..end];
+            let first = start == 0;
+            let last = end == cnt;
+            start = end;
+
+            let len = Self::group_len(group);
+
+            // 只有单个不少于 2 个字节的读操作使用 dma 接收
+            let rx_dma = self
+                .rx_dma
+                .as_mut()
+                .filter(|_| is_read && group.len() == 1 && len >= 2);
+            if is_read {
+                Self::read_config(len, rx_dma.is_some())?;
+            }
+
+            Self::start_block(address, is_read, first)?;
+
+            if !is_read {
+                Self::write_group_block(self.tx_dma.as_mut(), group, len, last)?;
+            } else if let Some(dma) = rx_dma {
+                Self::read_group_dma_block(dma, group, last)?;
+            } else {
+                Self::read_group_block(group, len, last)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 等待事件，期间出现的错误直接返回，超时返回 `timeout`
+    fn wait_block(event: Event, timeout: Error) -> Result<(), Error> {
+        Self::wait_until_block(WAIT_FLAG_TIMEOUT, || T::event_flag(event), timeout)
+    }
+
+    /// 等待 `f` 返回 true，期间出现的错误直接返回，超时返回 `timeout`
+    fn wait_until_block(tick: usize, f: impl Fn() -> bool, timeout: Error) -> Result<(), Error> {
+        let rst = Cell::new(Ok(()));
+        wait_for_true_timeout_block(tick, || {
+            rst.set(T::check_error());
+            rst.get().is_err() || f()
+        })
+        .map_err(|_| timeout)?;
+        rst.get()
+    }
+
+    /// 发送起始条件（重复起始条件已经在上一组结束时发出）和地址
+    fn start_block(address: u8, is_read: bool, first: bool) -> Result<(), Error> {
+        if first {
+            T::start();
+        }
         // SB=1，通过读 SR1，再向 DR 寄存器写数据，实现对该位的清零
-        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::SB)).map_err(
-            |_| {
-                T::event_clear(Event::ARLO);
-                Error::Start
-            },
-        )?;
+        Self::wait_block(Event::SB, Error::Start)?;
 
-        T::transmit(address << 1);
+        T::transmit((address << 1) | is_read as u8);
 
         // ADDR=1，通过读 SR1，再读 SR2，实现对该位的清零
-        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::ADD)).map_err(
-            |_| {
-                // Self::debug();
-                // 清除 af 置位
-                T::event_clear(Event::AF);
-                T::stop();
-                Error::Address
-            },
-        )?;
+        Self::wait_block(Event::ADD, Error::Address).map_err(|e| match e {
+            Error::Nack => Error::Address,
+            e => e,
+        })?;
         T::event_clear(Event::ADD);
+        Ok(())
+    }
 
-        // TRA 位指示主设备是在接收器模式还是发送器模式。
-
-        let mut iter = buf.iter();
-        if let Some(d) = iter.next() {
-            // EV8_1：TxE=1, shift 寄存器 empty，数据寄存器 empty，向 DR 寄存器写 Data1
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::TXE))
-                .map_err(|_| Error::Tx)?;
-            T::transmit(*d);
+    fn write_group_block(
+        dma: Option<&mut DmaChannel<'d, DMA, Blocking>>,
+        group: &[Operation<'_>],
+        len: usize,
+        last: bool,
+    ) -> Result<(), Error> {
+        let bufs = group.iter().filter_map(|op| match op {
+            Operation::Write(buf) => Some(*buf),
+            Operation::Read(_) => None,
+        });
+
+        match dma {
+            Some(dma) => {
+                for buf in bufs.filter(|buf| !buf.is_empty()) {
+                    Self::dma_write_start(dma, buf);
+                    let rst = Self::wait_dma_block(dma, buf.len(), Error::Tx);
+                    Self::dma_finish(dma);
+                    rst?;
+                }
+            }
+            None => {
+                for v in bufs.flatten() {
+                    // EV8：TxE=1，向 DR 写入下一个数据
+                    Self::wait_block(Event::TXE, Error::Tx)?;
+                    T::transmit(*v);
+                }
+            }
         }
 
-        // 接着将后面的数据发送出去
-        for t in iter {
-            // EV8：TxE=1, shift 寄存器不 empty，数据寄存器 empty，向 DR 寄存器写 Data2，该位被清零
-            wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::TXE)).map_err(
-                |_| {
-                    T::stop();
-                    Error::Tx
-                },
-            )?;
-            T::transmit(*t);
+        // EV8_2：BTF=1，最后一个数据已经发送并收到应答
+        if len > 0 {
+            Self::wait_block(Event::BTF, Error::Tx)?;
         }
+        Self::end_group(last);
+        Ok(())
+    }
 
-        // EV8_2：TxE=1, BTF=1, 写 Stop 位寄存器，当硬件发出 Stop 位时，TxE 和 BTF 被清零
-        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::TXE)).map_err(
-            |_| {
-                T::stop();
-                Error::Tx
-            },
-        )?;
+    /// 按照参考手册的接收流程读取，最后两个字节利用 BTF 的时钟延展保证 NACK 和停止条件的时序
+    fn read_group_block(group: &mut [Operation<'_>], len: usize, last: bool) -> Result<(), Error> {
+        let mut bytes = group.iter_mut().flat_map(|op| match op {
+            Operation::Read(buf) => buf.iter_mut(),
+            Operation::Write(_) => Default::default(),
+        });
+        let mut next = || bytes.next().unwrap();
+
+        match len {
+            1 => {
+                Self::end_group(last);
+                Self::wait_block(Event::RXNE, Error::RX)?;
+                *next() = T::read();
+            }
+            2 => {
+                // POS=1，NACK 作用于第二个字节
+                T::ack(false);
+                Self::wait_block(Event::BTF, Error::RX)?;
+                Self::end_group(last);
+                *next() = T::read();
+                *next() = T::read();
+                T::clear_pos();
+            }
+            _ => {
+                for _ in 0..len - 3 {
+                    Self::wait_block(Event::RXNE, Error::RX)?;
+                    *next() = T::read();
+                }
 
-        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::TXE)).map_err(
-            |_| {
-                T::stop();
-                Error::Tx
-            },
-        )?;
+                // 倒数第三个字节在 DR 中，倒数第二个在移位寄存器中
+                Self::wait_block(Event::BTF, Error::RX)?;
+                T::ack(false);
+                *next() = T::read();
 
-        wait_for_true_timeout_block(WAIT_FLAG_TIMEOUT, || T::event_flag(Event::BTF)).map_err(
-            |_| {
-                T::stop();
-                Error::Tx
-            },
-        )?;
+                // 倒数第二个字节在 DR 中，最后一个在移位寄存器中
+                Self::wait_block(Event::BTF, Error::RX)?;
+                Self::end_group(last);
+                *next() = T::read();
 
-        T::stop();
+                Self::wait_block(Event::RXNE, Error::RX)?;
+                *next() = T::read();
+            }
+        }
+        Ok(())
+    }
 
-        Ok(buf.len())
+    /// 使用 dma 接收单个读操作，LAST=1，dma 传输的最后一个字节自动回复 NACK
+    fn read_group_dma_block(
+        dma: &mut DmaChannel<'d, DMA, Blocking>,
+        group: &mut [Operation<'_>],
+        last: bool,
+    ) -> Result<(), Error> {
+        let Some(Operation::Read(buf)) = group.first_mut() else {
+            return Err(Error::RX);
+        };
+
+        let len = buf.len();
+        Self::dma_read_start(dma, buf);
+        let rst = Self::wait_dma_block(dma, len, Error::RX);
+        Self::dma_finish(dma);
+        rst?;
+
+        Self::end_group(last);
+        Ok(())
     }
 
-    pub fn read_block(&self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
-        T::master_receive_block(address, buf)
+    /// 等待 dma 传输完成，超时时间按照传输的数量计算
+    fn wait_dma_block(
+        dma: &DmaChannel<'d, DMA, Blocking>,
+        len: usize,
+        timeout: Error,
+    ) -> Result<(), Error> {
+        let rst = Cell::new(Ok(()));
+        Self::wait_until_block(
+            WAIT_FLAG_TIMEOUT.saturating_mul(len),
+            || {
+                if dma.is_error() {
+                    rst.set(Err(Error::Dma));
+                }
+                rst.get().is_err() || dma.is_finish()
+            },
+            timeout,
+        )?;
+        rst.get()
     }
 }
 
@@ -131,17 +417,7 @@ impl<'d, T: Instance> embedded_hal::i2c::I2c for Master<'d, T, Blocking> {
         address: u8,
         operations: &mut [embedded_hal::i2c::Operation<'_>],
     ) -> Result<(), Self::Error> {
-        for op in operations {
-            match op {
-                Operation::Write(buf) => {
-                    self.write_block(address, buf)?;
-                }
-                Operation::Read(buf) => {
-                    self.read_block(address, buf)?;
-                }
-            }
-        }
-        Ok(())
+        self.transaction_block(address, operations)
     }
 }
 
@@ -169,10 +445,7 @@ impl<'d, T: Instance> embedded_hal_027::blocking::i2c::WriteRead for Master<'d,
         bytes: &[u8],
         buffer: &mut [u8],
     ) -> Result<(), Self::Error> {
-        self.write_block(address, bytes)
-            .map_or_else(Err, |_| Ok(()))?;
-        self.read_block(address, buffer)
-            .map_or_else(Err, |_| Ok(()))
+        self.write_read_block(address, bytes, buffer)
     }
 }
 
@@ -180,139 +453,262 @@ impl<'d, T: Instance> embedded_hal_027::blocking::i2c::WriteRead for Master<'d,
 
 #[cfg(feature = "embassy")]
 impl<'d, T: Instance> Master<'d, T, Async> {
-    pub async fn read(&self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
-        let block = T::block();
+    pub async fn read(&mut self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = buf.len();
+        self.transaction_async(address, &mut [Operation::Read(buf)])
+            .await?;
+        Ok(len)
+    }
 
-        T::start();
+    pub async fn write(&mut self, address: u8, buf: &[u8]) -> Result<usize, Error> {
+        self.transaction_async(address, &mut [Operation::Write(buf)])
+            .await?;
+        Ok(buf.len())
+    }
 
-        // EV5：SB=1, 先读 SR1 寄存器，再写 DR 寄存器，清零该位
-        EventFuture::<T>::new(EnumSet::empty() | Event::SB).await?;
+    /// 写入后发送重复起始条件再读取，中间没有停止条件
+    pub async fn write_read(
+        &mut self,
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
+    ) -> Result<(), Error> {
+        self.transaction_async(
+            address,
+            &mut [Operation::Write(write), Operation::Read(read)],
+        )
+        .await
+    }
 
-        T::transmit((address << 1) | 1);
+    /// 执行一组操作
+    ///
+    /// 相邻的同方向操作合并为一次传输，方向改变时发送重复起始条件和地址，最后发送停止条件。
+    /// 不支持长度为 0 的读取
+    async fn transaction_async(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        // 被取消时释放总线
+        let guard = DropGuard::new(|| {
+            T::disable_interrupts();
+            T::dma_enable(false);
+            T::dma_last(false);
+            T::stop();
+            T::clear_pos();
+        });
+        let rst = self.transfer(address, operations).await;
+        let _ = guard.into_inner();
+
+        Self::finish(rst)
+    }
 
-        // EV6：ADDR，先读 SR1，再读 SR2，清零该位
-        EventFuture::<T>::new(EnumSet::empty() | Event::ADD).await?;
+    async fn transfer(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        T::clear_pos();
 
-        let len = buf.len();
+        let cnt = operations.len();
+        let mut start = 0;
+        while start < cnt {
+            let is_read = matches!(operations[start], Operation::Read(_));
+            let end = Self::group_end(operations, start);
+            let group = &mut operations[start..end];
+            let first = start == 0;
+            let last = end == cnt;
+            start = end;
+
+            let len = Self::group_len(group);
+
+            // 只有单个不少于 2 个字节的读操作使用 dma 接收
+            let rx_dma = self
+                .rx_dma
+                .as_mut()
+                .filter(|_| is_read && group.len() == 1 && len >= 2);
+            if is_read {
+                Self::read_config(len, rx_dma.is_some())?;
+            }
 
-        let mut enumerate = buf.iter_mut().enumerate();
-        while let Some((idx, p)) = enumerate.next() {
-            let remain = len - idx;
-            if remain > 2 {
-                T::ack(true);
-                // EV7：RxNE=1, 读 DR 寄存器清零该位
-                EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
+            // 重复起始条件已经在上一组结束时发出
+            if first {
+                T::start();
+            }
+            EventFuture::<T>::new(EnumSet::empty() | Event::SB).await?;
+
+            T::transmit((address << 1) | is_read as u8);
 
-                // 读取数据
-                *p = block.dr.read().dr().bits();
+            // ADDR 在返回前已经被清除
+            EventFuture::<T>::new(EnumSet::empty() | Event::ADD)
+                .await
+                .map_err(|e| match e {
+                    Error::Nack => Error::Address,
+                    e => e,
+                })?;
+
+            if !is_read {
+                Self::write_group(self.tx_dma.as_mut(), group, len, last).await?;
+            } else if let Some(dma) = rx_dma {
+                Self::read_group_dma(dma, group, last).await?;
+            } else {
+                Self::read_group(group, len, last).await?;
+            }
+        }
 
-                if T::event_flag(Event::BTF) {
-                    T::ack(true);
-                    let (_, p) = enumerate.next().unwrap();
-                    *p = block.dr.read().dr().bits();
+        Ok(())
+    }
+
+    async fn write_group(
+        dma: Option<&mut DmaChannel<'d, DMA, Async>>,
+        group: &[Operation<'_>],
+        len: usize,
+        last: bool,
+    ) -> Result<(), Error> {
+        let bufs = group.iter().filter_map(|op| match op {
+            Operation::Write(buf) => Some(*buf),
+            Operation::Read(_) => None,
+        });
+
+        match dma {
+            Some(dma) => {
+                for buf in bufs.filter(|buf| !buf.is_empty()) {
+                    Self::dma_write_start(dma, buf);
+                    let rst = Self::wait_dma(dma).await;
+                    Self::dma_finish(dma);
+                    rst?;
                 }
-            } else if remain == 2 {
-                T::ack(false);
-                // EV7：RxNE=1, 读 DR 寄存器清零该位
-                EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
-                // 读取数据
-                *p = block.dr.read().dr().bits();
-                if T::event_flag(Event::BTF) {
-                    T::ack(false);
-                    let (_, p) = enumerate.next().unwrap();
-                    *p = block.dr.read().dr().bits();
+            }
+            None => {
+                for v in bufs.flatten() {
+                    // EV8：TxE=1，向 DR 写入下一个数据
+                    EventFuture::<T>::new(EnumSet::empty() | Event::TXE).await?;
+                    T::transmit(*v);
                 }
-            } else if remain == 1 {
-                T::ack(false);
-                // EV7：RxNE=1, 读 DR 寄存器清零该位
-                // EV7：RxNE=1, 读 DR 寄存器清零该位
-                EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
-                // 读取数据
-                *p = block.dr.read().dr().bits();
             }
         }
 
-        T::stop();
-
-        Ok(buf.len())
+        // EV8_2：BTF=1，最后一个数据已经发送并收到应答
+        if len > 0 {
+            EventFuture::<T>::new(EnumSet::empty() | Event::BTF).await?;
+        }
+        Self::end_group(last);
+        Ok(())
     }
 
-    pub async fn write(&mut self, address: u8, buf: &[u8]) -> Result<usize, Error> {
-        // 如果总线处于busy状态，则退出
-        T::clear_pos();
-        T::start();
-        // SB=1，通过读 SR1，再向 DR 寄存器写数据，实现对该位的清零
-        EventFuture::<T>::new(EnumSet::empty() | Event::SB).await?;
+    /// 按照参考手册的接收流程读取，最后两个字节利用 BTF 的时钟延展保证 NACK 和停止条件的时序
+    async fn read_group(group: &mut [Operation<'_>], len: usize, last: bool) -> Result<(), Error> {
+        let mut bytes = group.iter_mut().flat_map(|op| match op {
+            Operation::Read(buf) => buf.iter_mut(),
+            Operation::Write(_) => Default::default(),
+        });
+        let mut next = || bytes.next().unwrap();
+
+        match len {
+            1 => {
+                Self::end_group(last);
+                EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
+                *next() = T::read();
+            }
+            2 => {
+                // POS=1，NACK 作用于第二个字节
+                T::ack(false);
+                EventFuture::<T>::new(EnumSet::empty() | Event::BTF).await?;
+                Self::end_group(last);
+                *next() = T::read();
+                *next() = T::read();
+                T::clear_pos();
+            }
+            _ => {
+                for _ in 0..len - 3 {
+                    EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
+                    *next() = T::read();
+                }
 
-        T::transmit(address << 1);
+                // 倒数第三个字节在 DR 中，倒数第二个在移位寄存器中
+                EventFuture::<T>::new(EnumSet::empty() | Event::BTF).await?;
+                T::ack(false);
+                *next() = T::read();
 
-        // ADDR=1，通过读 SR1，再读 SR2，实现对该位的清零
-        EventFuture::<T>::new(EnumSet::empty() | Event::ADD)
-            .await
-            .inspect_err(|_| {
-                T::event_clear(Event::AF);
-                T::stop();
-            })?;
-        T::event_clear(Event::ADD);
+                // 倒数第二个字节在 DR 中，最后一个在移位寄存器中
+                EventFuture::<T>::new(EnumSet::empty() | Event::BTF).await?;
+                Self::end_group(last);
+                *next() = T::read();
 
-        // TRA 位指示主设备是在接收器模式还是发送器模式。
-        let mut iter = buf.iter();
-        if let Some(d) = iter.next() {
-            // EV8_1：TxE=1, shift 寄存器 empty，数据寄存器 empty，向 DR 寄存器写 Data1
-            EventFuture::<T>::new(EnumSet::empty() | Event::TXE).await?;
-            T::transmit(*d);
+                EventFuture::<T>::new(EnumSet::empty() | Event::RXNE).await?;
+                *next() = T::read();
+            }
         }
+        Ok(())
+    }
 
-        // 接着将后面的数据发送出去
-        for t in iter {
-            // EV8：TxE=1, shift 寄存器不 empty，数据寄存器 empty，向 DR 寄存器写 Data2，该位被清零
-            EventFuture::<T>::new(EnumSet::empty() | Event::TXE | Event::BTF)
-                .await
-                .inspect_err(|_| {
-                    T::stop();
-                })?;
+    /// 使用 dma 接收单个读操作，LAST=1，dma 传输的最后一个字节自动回复 NACK
+    async fn read_group_dma(
+        dma: &mut DmaChannel<'d, DMA, Async>,
+        group: &mut [Operation<'_>],
+        last: bool,
+    ) -> Result<(), Error> {
+        let Some(Operation::Read(buf)) = group.first_mut() else {
+            return Err(Error::RX);
+        };
+
+        Self::dma_read_start(dma, buf);
+        let rst = Self::wait_dma(dma).await;
+        Self::dma_finish(dma);
+        rst?;
+
+        Self::end_group(last);
+        Ok(())
+    }
 
-            T::transmit(*t);
-        }
+    /// 等待 dma 传输完成，期间出现的总线错误、仲裁丢失、应答失败和过载都会返回错误
+    async fn wait_dma(dma: &DmaChannel<'d, DMA, Async>) -> Result<(), Error> {
+        let events = dma::Event::TCIF | dma::Event::TEIF;
+        let rst = poll_fn(|cx| {
+            dma.register_waker(cx.waker(), events);
+            future::register_waker::<T>(cx.waker(), EnumSet::empty());
 
-        // EV8_2：TxE=1, BTF=1, 写 Stop 位寄存器，当硬件发出 Stop 位时，TxE 和 BTF 被清零
-        EventFuture::<T>::new(EnumSet::empty() | Event::TXE)
-            .await
-            .inspect_err(|_| {
-                T::stop();
-            })?;
+            if let Err(error) = T::check_error() {
+                return Poll::Ready(Err(error));
+            }
 
-        EventFuture::<T>::new(EnumSet::empty() | Event::TXE)
-            .await
-            .inspect_err(|_| {
-                T::stop();
-            })?;
+            if dma.is_error() {
+                return Poll::Ready(Err(Error::Dma));
+            }
 
-        EventFuture::<T>::new(EnumSet::empty() | Event::BTF)
-            .await
-            .inspect_err(|_| {
-                T::stop();
-            })?;
+            if dma.is_finish() {
+                return Poll::Ready(Ok(()));
+            }
 
-        T::stop();
+            Poll::Pending
+        })
+        .await;
 
-        Ok(buf.len())
+        dma.disable_event(events);
+        T::disable_interrupts();
+        rst
     }
 }
 
 impl embedded_hal_async::i2c::Error for Error {
     fn kind(&self) -> embedded_hal_async::i2c::ErrorKind {
+        use embedded_hal_async::i2c::{ErrorKind, NoAcknowledgeSource};
         match *self {
-            Self::Address => embedded_hal_async::i2c::ErrorKind::NoAcknowledge(
-                embedded_hal_async::i2c::NoAcknowledgeSource::Address,
-            ),
-            Self::Busy => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::PClock => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::RX => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::SpeedMode => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::Start => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::Stop => embedded_hal_async::i2c::ErrorKind::Other,
-            Self::Tx => embedded_hal_async::i2c::ErrorKind::Other,
+            Self::Address => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+            Self::Nack => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+            Self::ArbitrationLost => ErrorKind::ArbitrationLoss,
+            Self::Bus => ErrorKind::Bus,
+            Self::Overrun => ErrorKind::Overrun,
+            Self::Busy => ErrorKind::Other,
+            Self::PClock => ErrorKind::Other,
+            Self::RX => ErrorKind::Other,
+            Self::SpeedMode => ErrorKind::Other,
+            Self::Start => ErrorKind::Other,
+            Self::Stop => ErrorKind::Other,
+            Self::Tx => ErrorKind::Other,
+            Self::Dma => ErrorKind::Other,
+            Self::Abort => ErrorKind::Other,
+            Self::InvalidArgument => ErrorKind::Other,
         }
     }
 }
@@ -324,20 +720,34 @@ impl<'d, T: Instance> embedded_hal_async::i2c::ErrorType for Master<'d, T, Async
 
 #[cfg(feature = "embassy")]
 impl<'d, T: Instance> embedded_hal_async::i2c::I2c for Master<'d, T, Async> {
-    async fn read(&mut self, _address: u8, _read: &mut [u8]) -> Result<(), Self::Error> {
-        todo!()
+    async fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), Self::Error> {
+        self.transaction_async(address, &mut [Operation::Read(read)])
+            .await
     }
 
-    async fn transaction(
+    async fn write(&mut self, address: u8, write: &[u8]) -> Result<(), Self::Error> {
+        self.transaction_async(address, &mut [Operation::Write(write)])
+            .await
+    }
+
+    async fn write_read(
         &mut self,
-        _address: u8,
-        _operations: &mut [embedded_hal_async::i2c::Operation<'_>],
+        address: u8,
+        write: &[u8],
+        read: &mut [u8],
     ) -> Result<(), Self::Error> {
-        todo!()
+        self.transaction_async(
+            address,
+            &mut [Operation::Write(write), Operation::Read(read)],
+        )
+        .await
     }
 
-    async fn write(&mut self, _address: u8, _write: &[u8]) -> Result<(), Self::Error> {
-        // self.write(address, write).await.map_or_else((), |e| e)
-        todo!()
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_async(address, operations).await
     }
 }
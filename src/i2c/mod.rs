@@ -3,19 +3,25 @@ mod future;
 mod hal;
 pub mod master;
 mod pins;
+#[cfg(feature = "embassy")]
+pub mod register;
 pub mod slave;
 
 use crate::clock::peripheral::{
     PeripheralClockIndex, PeripheralIdToClockIndex, PeripheralInterrupt,
 };
+use crate::dma::DmaChannel;
 use crate::gpio::{self, AnyPin};
 use crate::macro_def::{impl_sealed_peripheral_id, pin_af_for_instance_def};
+use crate::mcu::peripherals::DMA;
 use crate::mode::Mode;
 use core::marker::PhantomData;
 use embassy_hal_internal::{into_ref, Peripheral, PeripheralRef};
 use enumset::EnumSetType;
 pub use master::Master;
-pub use slave::Slave;
+#[cfg(feature = "embassy")]
+pub use register::{RegisterFlag, RegisterTarget, RegisterWrite};
+pub use slave::{Command, Slave, SlaveConfig};
 
 pub trait Instance: Peripheral<P = Self> + hal::sealed::Instance + 'static + Send {}
 
@@ -64,6 +70,20 @@ pub enum Error {
     Stop,
     Tx,
     RX,
+    /// 地址之后的数据没有应答
+    Nack,
+    /// 仲裁丢失
+    ArbitrationLost,
+    /// 总线错误，检测到错位的起始或停止条件
+    Bus,
+    /// 过载或欠载
+    Overrun,
+    /// dma 传输错误
+    Dma,
+    /// 主机没有正常结束传输，从机发送时没有收到 NACK 就出现了停止或重复起始条件
+    Abort,
+    /// 参数不合法
+    InvalidArgument,
 }
 
 pin_af_for_instance_def!(SdaPin, Instance);
@@ -76,6 +96,8 @@ pub struct AnyI2c<'d, T: Instance, M: Mode> {
     _mode: PhantomData<M>,
     _sda: PeripheralRef<'d, AnyPin>,
     _scl: PeripheralRef<'d, AnyPin>,
+    rx_dma: Option<DmaChannel<'d, DMA, M>>,
+    tx_dma: Option<DmaChannel<'d, DMA, M>>,
 }
 
 impl<'d, T: Instance, M: Mode> AnyI2c<'d, T, M> {
@@ -85,18 +107,23 @@ impl<'d, T: Instance, M: Mode> AnyI2c<'d, T, M> {
         Ok(())
     }
 
+    /// 作为主机使用，创建时提供的 dma 通道用于主机的收发
     pub fn as_master(self) -> Master<'d, T, M> {
-        Master::<'_, T, M>::new()
+        Master::<'_, T, M>::new(self.rx_dma, self.tx_dma)
     }
 
-    pub fn as_slave() -> Slave<'d, T, M> {
-        todo!()
+    pub fn as_slave(self, config: SlaveConfig) -> Slave<'d, T, M> {
+        Slave::<'_, T, M>::new(config)
     }
 
     pub fn new(
         _i2c: impl Peripheral<P = T>,
         scl: impl Peripheral<P = impl SclPin<T>> + 'd,
         sda: impl Peripheral<P = impl SdaPin<T>> + 'd,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
         config: Config,
     ) -> Result<Self, Error> {
         into_ref!(_i2c, scl, sda);
@@ -114,6 +141,8 @@ impl<'d, T: Instance, M: Mode> AnyI2c<'d, T, M> {
             _mode: PhantomData,
             _sda: sda.map_into(),
             _scl: scl.map_into(),
+            rx_dma,
+            tx_dma,
         })
     }
 }
@@ -0,0 +1,148 @@
+//! 寄存器映射的 I2C 从机
+//!
+//! 模拟常见的传感器或 EEPROM：主机写入的第一个字节是寄存器指针，之后的数据从指针处开始写入，
+//! 读取时从指针处开始发送，每个字节后指针自动加一，超过寄存器组末尾时回到 0。
+//! 指针在传输之间保持不变，先写入指针再使用重复起始条件读取即可读出指定的寄存器。
+//!
+//! 只读寄存器忽略主机的写入，标记了 [`RegisterFlag::Notify`] 的寄存器被写入后通过通道通知应用。
+//!
+//! ```rust, ignore
+//! static WRITES: Channel<CriticalSectionRawMutex, RegisterWrite, 8> = Channel::new();
+//!
+//! let mut registers = [0u8; 16];
+//! let mut flags = [EnumSet::empty(); 16];
+//! flags[0] = RegisterFlag::ReadOnly.into();
+//! flags[4] = RegisterFlag::Notify.into();
+//!
+//! let slave = i2c1.as_slave(SlaveConfig::new(0x3c));
+//! let mut target = RegisterTarget::new(slave, &mut registers, &flags, WRITES.sender().into())?;
+//! loop {
+//!     target.process().await?;
+//! }
+//! ```
+
+use super::{Command, Error, Instance, Slave};
+use crate::mode::Async;
+use embassy_sync::channel::DynamicSender;
+use enumset::{EnumSet, EnumSetType};
+
+/// 寄存器属性
+#[derive(EnumSetType, Debug)]
+pub enum RegisterFlag {
+    /// 主机不能写入，写入的数据被忽略
+    ReadOnly,
+    /// 主机写入后通知应用
+    Notify,
+}
+
+/// 主机写入了一个标记为 [`RegisterFlag::Notify`] 的寄存器
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterWrite {
+    /// 寄存器地址
+    pub register: u8,
+    /// 写入的值
+    pub value: u8,
+}
+
+/// 寄存器映射的 I2C 从机
+pub struct RegisterTarget<'a, 'd, T: Instance> {
+    slave: Slave<'d, T, Async>,
+    registers: &'a mut [u8],
+    flags: &'a [EnumSet<RegisterFlag>],
+    sender: DynamicSender<'a, RegisterWrite>,
+    pointer: usize,
+}
+
+impl<'a, 'd, T: Instance> RegisterTarget<'a, 'd, T> {
+    /// 新建寄存器从机，`flags` 与 `registers` 一一对应，寄存器数量为 1 ~ 256
+    ///
+    /// 寄存器数量超出范围或者与 `flags` 的数量不一致时返回 [`Error::InvalidArgument`]
+    pub fn new(
+        slave: Slave<'d, T, Async>,
+        registers: &'a mut [u8],
+        flags: &'a [EnumSet<RegisterFlag>],
+        sender: DynamicSender<'a, RegisterWrite>,
+    ) -> Result<Self, Error> {
+        if registers.is_empty() || registers.len() > 256 || registers.len() != flags.len() {
+            return Err(Error::InvalidArgument);
+        }
+
+        Ok(Self {
+            slave,
+            registers,
+            flags,
+            sender,
+            pointer: 0,
+        })
+    }
+
+    /// 处理一次主机的读或写
+    ///
+    /// 通道已满时写入通知被丢弃，不会让主机等待
+    pub async fn process(&mut self) -> Result<(), Error> {
+        let len = self.registers.len();
+
+        match self.slave.listen().await? {
+            Command::Read => {
+                let (registers, start) = (&*self.registers, self.pointer);
+                let cnt = self
+                    .slave
+                    .respond_with(|i| registers[(start + i) % len])
+                    .await?;
+                self.pointer = (start + cnt) % len;
+            }
+            Command::Write => {
+                let Self {
+                    registers,
+                    flags,
+                    sender,
+                    pointer,
+                    ..
+                } = self;
+                // 第一个字节是寄存器指针
+                let mut first = true;
+                self.slave
+                    .receive_with(|v| {
+                        if first {
+                            first = false;
+                            *pointer = v as usize % len;
+                            return;
+                        }
+
+                        let flag = flags[*pointer];
+                        if !flag.contains(RegisterFlag::ReadOnly) {
+                            registers[*pointer] = v;
+                            if flag.contains(RegisterFlag::Notify) {
+                                let _ = sender.try_send(RegisterWrite {
+                                    register: *pointer as u8,
+                                    value: v,
+                                });
+                            }
+                        }
+                        *pointer = (*pointer + 1) % len;
+                    })
+                    .await?;
+            }
+            // 广播呼叫不访问寄存器
+            Command::GeneralCall => {
+                self.slave.receive_with(|_| {}).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 当前的寄存器指针
+    pub fn pointer(&self) -> u8 {
+        self.pointer as u8
+    }
+
+    /// 寄存器组，在两次 [`Self::process`] 之间更新主机读取的值
+    pub fn registers_mut(&mut self) -> &mut [u8] {
+        self.registers
+    }
+
+    /// 返回内部的从机对象
+    pub fn inner(&mut self) -> &mut Slave<'d, T, Async> {
+        &mut self.slave
+    }
+}
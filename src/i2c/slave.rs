@@ -1,17 +1,282 @@
-use super::Instance;
-use crate::mode::{Blocking, Mode};
+#[cfg(feature = "embassy")]
+use super::future::EventFuture;
+use super::{Error, Event, Instance};
+#[cfg(feature = "embassy")]
+use crate::mode::Async;
+use crate::{
+    clock::peripheral::PeripheralInterrupt,
+    mode::{Blocking, Mode},
+};
 use core::marker::PhantomData;
+#[cfg(feature = "embassy")]
+use enumset::EnumSet;
+
+/// 从机配置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlaveConfig {
+    /// 7 位从机地址
+    pub address: u8,
+    /// 同时响应广播呼叫地址 0x00
+    pub general_call: bool,
+    /// 开启时钟延展，来不及处理数据时拉低 SCL 让主机等待，关闭时可能出现过载/欠载错误
+    pub clock_stretch: bool,
+}
+
+impl SlaveConfig {
+    /// 使用 7 位地址，不响应广播呼叫，开启时钟延展
+    pub fn new(address: u8) -> Self {
+        Self {
+            address,
+            general_call: false,
+            clock_stretch: true,
+        }
+    }
+
+    pub fn general_call(self, general_call: bool) -> Self {
+        Self {
+            general_call,
+            ..self
+        }
+    }
+
+    pub fn clock_stretch(self, clock_stretch: bool) -> Self {
+        Self {
+            clock_stretch,
+            ..self
+        }
+    }
+}
+
+/// 地址匹配后主机请求的操作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// 主机读取，从机发送数据
+    Read,
+    /// 主机写入，从机接收数据
+    Write,
+    /// 主机通过广播呼叫地址写入
+    GeneralCall,
+}
 
 /// Slave 角色
 pub struct Slave<'d, T: Instance, M: Mode> {
     _t: PhantomData<(&'d T, M)>,
+    /// 传输中已经处理的重复起始条件的地址匹配
+    #[cfg(feature = "embassy")]
+    pending: Option<Command>,
 }
 
 impl<'d, T: Instance, M: Mode> Slave<'d, T, M> {
-    // pub(super) fn new() -> Self {
-    //     todo!();
-    //     // Self { _t: PhantomData }
-    // }
+    pub(super) fn new(config: SlaveConfig) -> Self {
+        T::slave_config(config.address, config.general_call, config.clock_stretch);
+        if M::is_async() {
+            T::id().enable_interrupt();
+        }
+        Self {
+            _t: PhantomData,
+            #[cfg(feature = "embassy")]
+            pending: None,
+        }
+    }
+
+    /// 根据地址匹配后的传输方向返回主机请求的操作
+    fn command() -> Command {
+        match T::slave_direction() {
+            (_, true) => Command::GeneralCall,
+            (true, false) => Command::Read,
+            (false, false) => Command::Write,
+        }
+    }
 }
 
-impl<'d, T: Instance> Slave<'d, T, Blocking> {}
+impl<'d, T: Instance, M: Mode> Drop for Slave<'d, T, M> {
+    fn drop(&mut self) {
+        // 不再应答自己的地址
+        T::ack(false);
+        if M::is_async() {
+            T::id().disable_interrupt();
+        }
+    }
+}
+
+impl<'d, T: Instance> Slave<'d, T, Blocking> {
+    /// 等待地址匹配，返回主机请求的操作
+    ///
+    /// 上一次传输留下的停止标志被清除
+    pub fn listen_block(&mut self) -> Result<Command, Error> {
+        if T::event_flag(Event::STOPF) {
+            T::event_clear(Event::STOPF);
+        }
+        T::ack(true);
+
+        while !T::event_flag(Event::ADD) {
+            T::check_error()?;
+        }
+
+        T::event_clear(Event::ADD);
+        Ok(Self::command())
+    }
+
+    /// 接收主机写入的数据，直到停止条件或者重复起始条件，返回接收的数量
+    ///
+    /// `buf` 写满后之后的数据仍然回复 ACK，但是被丢弃。
+    /// 重复起始条件的地址匹配留给下一次 [`Self::listen_block`] 处理
+    pub fn read_block(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut cnt = 0;
+
+        loop {
+            // 先取走数据，停止条件可能紧跟在最后一个字节之后
+            if T::event_flag(Event::RXNE) {
+                let v = T::read();
+                // 回复 NACK 后从机不会置位 STOPF，所以写满后只丢弃数据
+                if let Some(d) = buf.get_mut(cnt) {
+                    *d = v;
+                    cnt += 1;
+                }
+                continue;
+            }
+
+            if T::event_flag(Event::STOPF) {
+                T::event_clear(Event::STOPF);
+                return Ok(cnt);
+            }
+
+            if T::event_flag(Event::ADD) {
+                return Ok(cnt);
+            }
+
+            T::check_error()?;
+        }
+    }
+
+    /// 发送数据给主机，直到主机回复 NACK、停止条件或者重复起始条件，返回主机确认接收的数量
+    ///
+    /// `buf` 发送完后主机还在读取时发送 0xff
+    pub fn write_block(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let mut cnt: usize = 0;
+
+        loop {
+            if T::event_flag(Event::AF) {
+                // 主机对最后一个字节回复 NACK，数据寄存器中已经写入的下一个字节不会被发送
+                T::event_clear(Event::AF);
+                return Ok(cnt.saturating_sub(1).min(buf.len()));
+            }
+
+            if T::event_flag(Event::TXE) {
+                T::transmit(buf.get(cnt).copied().unwrap_or(0xff));
+                cnt += 1;
+                continue;
+            }
+
+            if T::event_flag(Event::STOPF) {
+                T::event_clear(Event::STOPF);
+                return Ok(cnt.min(buf.len()));
+            }
+
+            if T::event_flag(Event::ADD) {
+                return Ok(cnt.min(buf.len()));
+            }
+
+            T::check_error()?;
+        }
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> Slave<'d, T, Async> {
+    /// 等待地址匹配，返回主机请求的操作
+    ///
+    /// 上一次传输中出现的重复起始条件在这里返回
+    pub async fn listen(&mut self) -> Result<Command, Error> {
+        if let Some(command) = self.pending.take() {
+            return Ok(command);
+        }
+
+        if T::event_flag(Event::STOPF) {
+            T::event_clear(Event::STOPF);
+        }
+        T::ack(true);
+
+        // ADDR 在返回前已经被清除
+        EventFuture::<T>::new(EnumSet::empty() | Event::ADD).await?;
+        Ok(Self::command())
+    }
+
+    /// 响应主机的读取，直到主机回复 NACK，返回主机确认接收的数量
+    ///
+    /// `buf` 发送完后主机还在读取时发送 0xff。没有收到 NACK 就出现停止或重复起始条件时返回
+    /// [`Error::Abort`]
+    pub async fn respond_to_read(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let cnt = self
+            .respond_with(|i| buf.get(i).copied().unwrap_or(0xff))
+            .await?;
+        Ok(cnt.min(buf.len()))
+    }
+
+    /// 接收主机写入的数据，直到停止或重复起始条件，返回接收的数量
+    ///
+    /// `buf` 写满后之后的数据仍然回复 ACK，但是被丢弃
+    pub async fn read_write(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut cnt = 0;
+        self.receive_with(|v| {
+            if let Some(d) = buf.get_mut(cnt) {
+                *d = v;
+                cnt += 1;
+            }
+        })
+        .await?;
+        Ok(cnt)
+    }
+
+    /// 逐个字节响应主机的读取，`next` 的参数为字节的序号，返回主机接收的数量
+    pub(super) async fn respond_with(
+        &mut self,
+        mut next: impl FnMut(usize) -> u8,
+    ) -> Result<usize, Error> {
+        let mut cnt: usize = 0;
+
+        loop {
+            let events = EnumSet::empty() | Event::TXE | Event::STOPF | Event::ADD;
+            match EventFuture::<T>::new(events).await {
+                Ok(Event::TXE) => {
+                    T::transmit(next(cnt));
+                    cnt += 1;
+                }
+                Ok(event) => {
+                    self.end(event);
+                    return Err(Error::Abort);
+                }
+                // 主机对最后一个字节回复 NACK，数据寄存器中已经写入的下一个字节不会被发送
+                Err(Error::Nack) => return Ok(cnt.saturating_sub(1)),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 逐个字节接收主机写入的数据，直到停止或重复起始条件
+    ///
+    /// 每个字节都回复 ACK，回复 NACK 后从机不会置位 STOPF，不需要的数据由 `sink` 丢弃
+    pub(super) async fn receive_with(&mut self, mut sink: impl FnMut(u8)) -> Result<(), Error> {
+        loop {
+            let events = EnumSet::empty() | Event::RXNE | Event::STOPF | Event::ADD;
+            match EventFuture::<T>::new(events).await? {
+                Event::RXNE => sink(T::read()),
+                event => {
+                    // 停止条件之前的最后一个字节可能还在 DR 中
+                    if T::event_flag(Event::RXNE) {
+                        sink(T::read());
+                    }
+                    self.end(event);
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// 传输结束，记录重复起始条件的地址匹配
+    fn end(&mut self, event: Event) {
+        if event == Event::ADD {
+            self.pending = Some(Self::command());
+        }
+    }
+}
@@ -21,6 +21,7 @@ pub mod dma;
 mod embassy;
 pub mod exti;
 pub mod flash;
+pub mod framing;
 pub mod gpio;
 pub mod i2c;
 #[cfg(not(feature = "embassy"))]
@@ -30,12 +31,15 @@ extern crate alloc;
 pub mod iwdg;
 mod macro_def;
 pub mod mcu;
+pub mod modbus;
 pub(crate) mod pwr;
 pub mod rtc;
+pub mod shell;
 pub mod spi;
 pub mod syscfg;
 pub mod timer;
 pub mod usart;
+pub mod xmodem;
 
 #[doc(hidden)]
 pub mod prelude {
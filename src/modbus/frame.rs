@@ -0,0 +1,167 @@
+//! RTU 帧的 CRC、时间间隔和接收缓冲，不依赖硬件
+
+use super::{Error, MAX_ADU_LEN};
+
+/// Modbus CRC16，多项式 0xA001（反射），初值 0xFFFF
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, v| {
+        let mut crc = crc ^ *v as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xa001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// 在 `buf[..len]` 后追加 CRC（低字节在前），返回新的长度
+pub fn append_crc(buf: &mut [u8], len: usize) -> usize {
+    let crc = crc16(&buf[..len]);
+    buf[len..len + 2].copy_from_slice(&crc.to_le_bytes());
+    len + 2
+}
+
+/// 检查 ADU 的长度和 CRC，返回去掉 CRC 的部分
+pub fn check_crc(adu: &[u8]) -> Result<&[u8], Error> {
+    // 地址、功能码和 CRC 至少 4 个字节
+    if adu.len() < 4 {
+        return Err(Error::Crc);
+    }
+    let (data, crc) = adu.split_at(adu.len() - 2);
+    if crc16(data).to_le_bytes() != crc {
+        return Err(Error::Crc);
+    }
+    Ok(data)
+}
+
+/// 一个字符（起始位、8 位数据、校验或停止位、停止位共 11 位）的时间，单位微秒
+pub fn char_time_us(baud: u32) -> u32 {
+    11_000_000u32.div_ceil(baud)
+}
+
+/// 帧间隔 t3.5，单位微秒，波特率高于 19200 时固定为 1750 微秒
+pub fn frame_gap_us(baud: u32) -> u32 {
+    if baud > 19200 {
+        1750
+    } else {
+        38_500_000u32.div_ceil(baud)
+    }
+}
+
+/// 空闲检测到帧结束时距离 t3.5 还需要等待的时间，单位微秒
+///
+/// 串口的空闲检测需要一个字符时间的空闲
+pub fn idle_gap_us(baud: u32) -> u32 {
+    frame_gap_us(baud).saturating_sub(char_time_us(baud))
+}
+
+/// 帧接收缓冲区
+pub struct FrameBuffer {
+    buf: [u8; MAX_ADU_LEN],
+    len: usize,
+    error: Option<Error>,
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBuffer {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; MAX_ADU_LEN],
+            len: 0,
+            error: None,
+        }
+    }
+
+    /// 开始新的一帧
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.error = None;
+    }
+
+    /// 追加一个字节，超过最大长度时整帧作废
+    pub fn push(&mut self, byte: u8) {
+        if self.len == MAX_ADU_LEN {
+            self.invalidate(Error::Overflow);
+            return;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+
+    /// 接收过程中出错，整帧作废，保留第一个错误
+    pub fn invalidate(&mut self, error: Error) {
+        self.error.get_or_insert(error);
+    }
+
+    /// 帧结束后调用，返回包含 CRC 的完整 ADU
+    pub fn adu(&self) -> Result<&[u8], Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(&self.buf[..self.len]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_vector() {
+        let mut buf = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0a, 0, 0];
+        assert_eq!(crc16(&buf[..6]), 0xcdc5);
+        assert_eq!(append_crc(&mut buf, 6), 8);
+        assert_eq!(buf[6..], [0xc5, 0xcd]);
+
+        assert_eq!(crc16(&[]), 0xffff);
+        assert_eq!(crc16(&[0x01, 0x06, 0x00, 0x01, 0x00, 0x03]), 0x0b98);
+    }
+
+    #[test]
+    fn crc_check() {
+        let adu = [0x01, 0x03, 0x00, 0x00, 0x00, 0x0a, 0xc5, 0xcd];
+        assert_eq!(check_crc(&adu), Ok(&adu[..6]));
+
+        let mut bad = adu;
+        bad[7] ^= 1;
+        assert_eq!(check_crc(&bad), Err(Error::Crc));
+        assert_eq!(check_crc(&adu[..3]), Err(Error::Crc));
+    }
+
+    #[test]
+    fn gaps() {
+        assert_eq!(char_time_us(9600), 1146);
+        assert_eq!(frame_gap_us(9600), 4011);
+        assert_eq!(idle_gap_us(9600), 4011 - 1146);
+
+        // 高于 19200 时使用固定的 t3.5
+        assert_eq!(frame_gap_us(115_200), 1750);
+        assert_eq!(idle_gap_us(115_200), 1750 - 96);
+    }
+
+    #[test]
+    fn frame_buffer() {
+        let mut frame = FrameBuffer::new();
+        frame.push(1);
+        frame.push(2);
+        assert_eq!(frame.adu(), Ok(&[1, 2][..]));
+
+        frame.invalidate(Error::Timeout);
+        frame.invalidate(Error::Crc);
+        assert_eq!(frame.adu(), Err(Error::Timeout));
+
+        frame.reset();
+        for i in 0..=MAX_ADU_LEN {
+            frame.push(i as u8);
+        }
+        assert_eq!(frame.adu(), Err(Error::Overflow));
+    }
+}
@@ -0,0 +1,317 @@
+//! 主机请求编码和响应解析，不依赖硬件
+
+use super::frame::{append_crc, check_crc};
+use super::{Error, Exception, FunctionCode, MAX_ADU_LEN};
+
+/// 主机请求
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request<'a> {
+    ReadCoils { addr: u16, quantity: u16 },
+    ReadDiscreteInputs { addr: u16, quantity: u16 },
+    ReadHoldingRegisters { addr: u16, quantity: u16 },
+    ReadInputRegisters { addr: u16, quantity: u16 },
+    WriteSingleCoil { addr: u16, value: bool },
+    WriteSingleRegister { addr: u16, value: u16 },
+    WriteMultipleCoils { addr: u16, values: &'a [bool] },
+    WriteMultipleRegisters { addr: u16, values: &'a [u16] },
+}
+
+impl Request<'_> {
+    pub fn function(&self) -> FunctionCode {
+        match self {
+            Self::ReadCoils { .. } => FunctionCode::ReadCoils,
+            Self::ReadDiscreteInputs { .. } => FunctionCode::ReadDiscreteInputs,
+            Self::ReadHoldingRegisters { .. } => FunctionCode::ReadHoldingRegisters,
+            Self::ReadInputRegisters { .. } => FunctionCode::ReadInputRegisters,
+            Self::WriteSingleCoil { .. } => FunctionCode::WriteSingleCoil,
+            Self::WriteSingleRegister { .. } => FunctionCode::WriteSingleRegister,
+            Self::WriteMultipleCoils { .. } => FunctionCode::WriteMultipleCoils,
+            Self::WriteMultipleRegisters { .. } => FunctionCode::WriteMultipleRegisters,
+        }
+    }
+
+    /// 编码为包含 CRC 的 ADU，返回长度
+    ///
+    /// 数量超出协议规定的范围时返回 [`Error::InvalidRequest`]
+    pub fn encode(&self, unit: u8, buf: &mut [u8; MAX_ADU_LEN]) -> Result<usize, Error> {
+        let check = |quantity: usize, max: usize| {
+            if quantity == 0 || quantity > max {
+                Err(Error::InvalidRequest)
+            } else {
+                Ok(quantity as u16)
+            }
+        };
+
+        let (addr, value) = match *self {
+            Self::ReadCoils { addr, quantity } | Self::ReadDiscreteInputs { addr, quantity } => {
+                (addr, check(quantity as usize, 2000)?)
+            }
+            Self::ReadHoldingRegisters { addr, quantity }
+            | Self::ReadInputRegisters { addr, quantity } => (addr, check(quantity as usize, 125)?),
+            Self::WriteSingleCoil { addr, value } => (addr, if value { 0xff00 } else { 0 }),
+            Self::WriteSingleRegister { addr, value } => (addr, value),
+            Self::WriteMultipleCoils { addr, values } => (addr, check(values.len(), 1968)?),
+            Self::WriteMultipleRegisters { addr, values } => (addr, check(values.len(), 123)?),
+        };
+
+        buf[0] = unit;
+        buf[1] = self.function() as u8;
+        buf[2..4].copy_from_slice(&addr.to_be_bytes());
+        buf[4..6].copy_from_slice(&value.to_be_bytes());
+        let mut len = 6;
+
+        match *self {
+            Self::WriteMultipleCoils { values, .. } => {
+                let bytes = values.len().div_ceil(8);
+                buf[6] = bytes as u8;
+                buf[7..7 + bytes].fill(0);
+                for (i, v) in values.iter().enumerate() {
+                    buf[7 + i / 8] |= (*v as u8) << (i % 8);
+                }
+                len = 7 + bytes;
+            }
+            Self::WriteMultipleRegisters { values, .. } => {
+                buf[6] = (values.len() * 2) as u8;
+                for (i, v) in values.iter().enumerate() {
+                    buf[7 + i * 2..9 + i * 2].copy_from_slice(&v.to_be_bytes());
+                }
+                len = 7 + values.len() * 2;
+            }
+            _ => {}
+        }
+
+        Ok(append_crc(buf, len))
+    }
+}
+
+/// 检查响应的 CRC、地址和功能码，返回功能码之后的数据
+///
+/// 异常响应返回 [`Error::Exception`]
+pub fn parse_response(unit: u8, function: FunctionCode, adu: &[u8]) -> Result<&[u8], Error> {
+    let data = check_crc(adu)?;
+    if data[0] != unit {
+        return Err(Error::InvalidResponse);
+    }
+
+    let code = function as u8;
+    if data[1] == code | 0x80 {
+        return match data.get(2) {
+            Some(v) if data.len() == 3 => Err(Error::Exception(Exception::from_u8(*v))),
+            _ => Err(Error::InvalidResponse),
+        };
+    }
+    if data[1] != code {
+        return Err(Error::InvalidResponse);
+    }
+    Ok(&data[2..])
+}
+
+/// 解析读线圈或离散输入的响应数据
+pub fn decode_bits(data: &[u8], out: &mut [bool]) -> Result<(), Error> {
+    let bytes = out.len().div_ceil(8);
+    if data.len() != 1 + bytes || data[0] as usize != bytes {
+        return Err(Error::InvalidResponse);
+    }
+    for (i, v) in out.iter_mut().enumerate() {
+        *v = data[1 + i / 8] & (1 << (i % 8)) != 0;
+    }
+    Ok(())
+}
+
+/// 解析读寄存器的响应数据
+pub fn decode_registers(data: &[u8], out: &mut [u16]) -> Result<(), Error> {
+    let bytes = out.len() * 2;
+    if data.len() != 1 + bytes || data[0] as usize != bytes {
+        return Err(Error::InvalidResponse);
+    }
+    for (i, v) in out.iter_mut().enumerate() {
+        *v = u16::from_be_bytes([data[1 + i * 2], data[2 + i * 2]]);
+    }
+    Ok(())
+}
+
+/// 检查写请求的响应是否是请求前 4 个字节的回显
+pub fn check_write_echo(request: &[u8], data: &[u8]) -> Result<(), Error> {
+    if data != &request[2..6] {
+        return Err(Error::InvalidResponse);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(request: Request<'_>) -> ([u8; MAX_ADU_LEN], usize) {
+        let mut buf = [0; MAX_ADU_LEN];
+        let len = request.encode(1, &mut buf).unwrap();
+        (buf, len)
+    }
+
+    /// 追加 CRC 后的响应
+    fn response(data: &[u8]) -> ([u8; MAX_ADU_LEN], usize) {
+        let mut buf = [0; MAX_ADU_LEN];
+        buf[..data.len()].copy_from_slice(data);
+        let len = append_crc(&mut buf, data.len());
+        (buf, len)
+    }
+
+    #[test]
+    fn encode_read() {
+        let (buf, len) = encode(Request::ReadHoldingRegisters {
+            addr: 0,
+            quantity: 10,
+        });
+        assert_eq!(buf[..len], [0x01, 0x03, 0x00, 0x00, 0x00, 0x0a, 0xc5, 0xcd]);
+
+        let (buf, len) = encode(Request::ReadCoils {
+            addr: 0x0013,
+            quantity: 0x25,
+        });
+        assert_eq!(buf[..len - 2], [0x01, 0x01, 0x00, 0x13, 0x00, 0x25]);
+    }
+
+    #[test]
+    fn encode_write() {
+        let (buf, len) = encode(Request::WriteSingleCoil {
+            addr: 0x00ac,
+            value: true,
+        });
+        assert_eq!(buf[..len - 2], [0x01, 0x05, 0x00, 0xac, 0xff, 0x00]);
+
+        let (buf, len) = encode(Request::WriteSingleRegister { addr: 1, value: 3 });
+        assert_eq!(buf[..len], [0x01, 0x06, 0x00, 0x01, 0x00, 0x03, 0x98, 0x0b]);
+
+        let values = [
+            true, false, true, true, false, false, true, true, true, false,
+        ];
+        let (buf, len) = encode(Request::WriteMultipleCoils {
+            addr: 0x0013,
+            values: &values,
+        });
+        assert_eq!(
+            buf[..len - 2],
+            [0x01, 0x0f, 0x00, 0x13, 0x00, 0x0a, 0x02, 0xcd, 0x01]
+        );
+
+        let (buf, len) = encode(Request::WriteMultipleRegisters {
+            addr: 0x0001,
+            values: &[0x000a, 0x0102],
+        });
+        assert_eq!(
+            buf[..len - 2],
+            [0x01, 0x10, 0x00, 0x01, 0x00, 0x02, 0x04, 0x00, 0x0a, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn encode_invalid() {
+        let mut buf = [0; MAX_ADU_LEN];
+        let requests = [
+            Request::ReadCoils {
+                addr: 0,
+                quantity: 0,
+            },
+            Request::ReadDiscreteInputs {
+                addr: 0,
+                quantity: 2001,
+            },
+            Request::ReadInputRegisters {
+                addr: 0,
+                quantity: 126,
+            },
+            Request::WriteMultipleCoils {
+                addr: 0,
+                values: &[],
+            },
+            Request::WriteMultipleRegisters {
+                addr: 0,
+                values: &[0; 124],
+            },
+        ];
+        for request in requests {
+            assert_eq!(request.encode(1, &mut buf), Err(Error::InvalidRequest));
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let (buf, len) = response(&[0x01, 0x03, 0x04, 0x00, 0x0a, 0x01, 0x02]);
+        let data = parse_response(1, FunctionCode::ReadHoldingRegisters, &buf[..len]).unwrap();
+        let mut out = [0; 2];
+        decode_registers(data, &mut out).unwrap();
+        assert_eq!(out, [0x000a, 0x0102]);
+
+        // 数量与请求不符
+        let mut out = [0; 3];
+        assert_eq!(
+            decode_registers(data, &mut out),
+            Err(Error::InvalidResponse)
+        );
+
+        let (buf, len) = response(&[0x01, 0x01, 0x02, 0xcd, 0x01]);
+        let data = parse_response(1, FunctionCode::ReadCoils, &buf[..len]).unwrap();
+        let mut out = [false; 10];
+        decode_bits(data, &mut out).unwrap();
+        assert_eq!(
+            out,
+            [true, false, true, true, false, false, true, true, true, false]
+        );
+    }
+
+    #[test]
+    fn parse_mismatch() {
+        let (buf, len) = response(&[0x01, 0x03, 0x02, 0x00, 0x0a]);
+        assert_eq!(
+            parse_response(2, FunctionCode::ReadHoldingRegisters, &buf[..len]),
+            Err(Error::InvalidResponse)
+        );
+        assert_eq!(
+            parse_response(1, FunctionCode::ReadInputRegisters, &buf[..len]),
+            Err(Error::InvalidResponse)
+        );
+
+        let mut bad = buf;
+        bad[len - 1] ^= 1;
+        assert_eq!(
+            parse_response(1, FunctionCode::ReadHoldingRegisters, &bad[..len]),
+            Err(Error::Crc)
+        );
+    }
+
+    #[test]
+    fn parse_exception() {
+        let (buf, len) = response(&[0x01, 0x83, 0x02]);
+        assert_eq!(
+            parse_response(1, FunctionCode::ReadHoldingRegisters, &buf[..len]),
+            Err(Error::Exception(Some(Exception::IllegalDataAddress)))
+        );
+
+        // 未知的异常码
+        let (buf, len) = response(&[0x01, 0x83, 0x0b]);
+        assert_eq!(
+            parse_response(1, FunctionCode::ReadHoldingRegisters, &buf[..len]),
+            Err(Error::Exception(None))
+        );
+
+        // 异常响应的长度不对
+        let (buf, len) = response(&[0x01, 0x83, 0x02, 0x00]);
+        assert_eq!(
+            parse_response(1, FunctionCode::ReadHoldingRegisters, &buf[..len]),
+            Err(Error::InvalidResponse)
+        );
+    }
+
+    #[test]
+    fn write_echo() {
+        let (request, _) = encode(Request::WriteSingleRegister { addr: 1, value: 3 });
+        assert_eq!(
+            check_write_echo(&request, &[0x00, 0x01, 0x00, 0x03]),
+            Ok(())
+        );
+        assert_eq!(
+            check_write_echo(&request, &[0x00, 0x01, 0x00, 0x04]),
+            Err(Error::InvalidResponse)
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Modbus RTU
+//!
+//! 帧的 CRC、时间间隔、请求处理和响应解析都在 [`frame`]、[`slave`]、[`master`] 中，
+//! 不依赖硬件，可以在主机上测试。
+//!
+//! 收发基于 [`AnyUsart`]，帧之间以 3.5 个字符时间（t3.5）的空闲分隔。定时器的精度为毫秒，
+//! 不足以测量字符级的间隔，因此使用串口的空闲检测（IDLE）判断帧结束：总线空闲一个字符的时间即认为
+//! 一帧结束。这比协议规定的 t1.5 更严格，帧内超过一个字符的间隔会把帧截断，截断的帧因为 CRC
+//! 错误被丢弃。收到一帧后在发送之前补足剩余的 t3.5 间隔。
+//!
+//! 需要 RS-485 时使用 [`AnyUsart::new_rs485`] 创建串口，发送时自动控制 DE 引脚，
+//! 并且应当开启 `mask_receiver`，否则主机会把自己发出的请求当作响应。
+//!
+//! ```rust, ignore
+//! struct Registers([u16; 8]);
+//!
+//! impl Handler for Registers {
+//!     fn read_holding_register(&mut self, addr: u16) -> Result<u16, Exception> {
+//!         self.0.get(addr as usize).copied().ok_or(Exception::IllegalDataAddress)
+//!     }
+//!     fn write_register(&mut self, addr: u16, value: u16) -> Result<(), Exception> {
+//!         let v = self.0.get_mut(addr as usize).ok_or(Exception::IllegalDataAddress)?;
+//!         *v = value;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let usart = AnyUsart::new_rs485(p.USART1, Some(rx), tx, de, None, None, config, Default::default());
+//! let mut slave = ModbusSlave::new(usart, 1);
+//! slave.run(&mut Registers([0; 8])).await;
+//! ```
+
+pub mod frame;
+pub mod master;
+pub mod slave;
+mod types;
+
+pub use master::Request;
+pub use slave::Handler;
+pub use types::*;
+
+#[cfg(feature = "embassy")]
+use crate::delay::delay_us;
+#[cfg(feature = "embassy")]
+use crate::mode::Async;
+#[cfg(feature = "embassy")]
+use crate::usart::{AnyUsart, Instance, UsartRx};
+#[cfg(feature = "embassy")]
+use embassy_time::{with_timeout, Duration, Timer};
+#[cfg(feature = "embassy")]
+use frame::FrameBuffer;
+
+/// 帧结束后等待到 t3.5，空闲检测时已经经过了一个字符的时间
+///
+/// 不足一个节拍时使用阻塞延时，否则使用定时器并多留一个节拍
+#[cfg(feature = "embassy")]
+async fn frame_gap(baud: u32) {
+    let us = frame::idle_gap_us(baud) as u64;
+    let tick = 1_000_000 / embassy_time::TICK_HZ;
+    if us < tick {
+        delay_us(us as usize);
+    } else {
+        Timer::after_micros(us + tick).await;
+    }
+}
+
+/// 接收一帧，`first` 为等待第一个字节的超时时间，`None` 表示一直等待
+///
+/// 总线空闲一个字符的时间即认为帧结束，接收过程中的串口错误会使整帧作废，但仍然等待到帧结束
+#[cfg(feature = "embassy")]
+async fn receive_frame<T: Instance>(
+    rx: &mut UsartRx<'_, T, Async>,
+    frame: &mut FrameBuffer,
+    first: Option<Duration>,
+) -> Result<(), Error> {
+    let mut byte = 0;
+    frame.reset();
+
+    // 第一个字节之前的空闲不算帧结束，读取数据时也会清除上一帧留下的空闲标志
+    let mut rst = match first {
+        Some(timeout) => with_timeout(timeout, rx.read(core::slice::from_mut(&mut byte)))
+            .await
+            .map_err(|_| Error::Timeout)?,
+        None => rx.read(core::slice::from_mut(&mut byte)).await,
+    };
+
+    loop {
+        match rst {
+            Ok(0) => return Ok(()),
+            Ok(_) => frame.push(byte),
+            Err(error) => frame.invalidate(error.into()),
+        }
+        rst = rx.read_with_idle(core::slice::from_mut(&mut byte)).await;
+    }
+}
+
+/// Modbus RTU 从机
+#[cfg(feature = "embassy")]
+pub struct ModbusSlave<'d, T: Instance> {
+    usart: AnyUsart<'d, T, Async>,
+    unit_id: u8,
+    frame: FrameBuffer,
+    response: [u8; MAX_ADU_LEN],
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> ModbusSlave<'d, T> {
+    /// 新建从机，`unit_id` 为本机地址 1 ~ 247
+    pub fn new(usart: AnyUsart<'d, T, Async>, unit_id: u8) -> Self {
+        assert!((1..=247).contains(&unit_id));
+        Self {
+            usart,
+            unit_id,
+            frame: FrameBuffer::new(),
+            response: [0; MAX_ADU_LEN],
+        }
+    }
+
+    /// 接收并处理一帧请求，需要时发送响应
+    ///
+    /// 作废的帧返回对应的错误，CRC 错误或地址不是本机的帧被忽略
+    pub async fn process<H: Handler + ?Sized>(&mut self, handler: &mut H) -> Result<(), Error> {
+        receive_frame(&mut self.usart.rx, &mut self.frame, None).await?;
+
+        let request = self.frame.adu()?;
+        if let Some(len) = slave::handle_request(self.unit_id, handler, request, &mut self.response)
+        {
+            frame_gap(self.usart.baud_rate()).await;
+            self.usart.tx.write(&self.response[..len]).await?;
+        }
+        Ok(())
+    }
+
+    /// 一直处理请求，忽略所有错误
+    pub async fn run<H: Handler + ?Sized>(&mut self, handler: &mut H) -> ! {
+        loop {
+            let _ = self.process(handler).await;
+        }
+    }
+
+    /// 返回内部的串口对象
+    pub fn inner(&mut self) -> &mut AnyUsart<'d, T, Async> {
+        &mut self.usart
+    }
+}
+
+/// Modbus RTU 主机
+#[cfg(feature = "embassy")]
+pub struct ModbusMaster<'d, T: Instance> {
+    usart: AnyUsart<'d, T, Async>,
+    timeout: Duration,
+    frame: FrameBuffer,
+    request: [u8; MAX_ADU_LEN],
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> ModbusMaster<'d, T> {
+    /// 新建主机，`timeout` 为发送请求后等待响应第一个字节的时间
+    pub fn new(usart: AnyUsart<'d, T, Async>, timeout: Duration) -> Self {
+        Self {
+            usart,
+            timeout,
+            frame: FrameBuffer::new(),
+            request: [0; MAX_ADU_LEN],
+        }
+    }
+
+    /// 修改响应超时时间
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// 返回内部的串口对象
+    pub fn inner(&mut self) -> &mut AnyUsart<'d, T, Async> {
+        &mut self.usart
+    }
+
+    /// 发送请求并等待响应，返回响应中功能码之后的数据
+    ///
+    /// 写请求会检查响应的回显，地址为 0 的广播请求只发送不等待响应，返回空的数据
+    pub async fn request(&mut self, unit: u8, request: &Request<'_>) -> Result<&[u8], Error> {
+        let function = request.function();
+        let is_write = !matches!(
+            function,
+            FunctionCode::ReadCoils
+                | FunctionCode::ReadDiscreteInputs
+                | FunctionCode::ReadHoldingRegisters
+                | FunctionCode::ReadInputRegisters
+        );
+        if unit > 247 || (unit == 0 && !is_write) {
+            return Err(Error::InvalidRequest);
+        }
+
+        let len = request.encode(unit, &mut self.request)?;
+        self.usart.tx.write(&self.request[..len]).await?;
+        if unit == 0 {
+            return Ok(&[]);
+        }
+
+        receive_frame(&mut self.usart.rx, &mut self.frame, Some(self.timeout)).await?;
+        // 保证下一个请求之前有足够的帧间隔
+        frame_gap(self.usart.baud_rate()).await;
+
+        let data = master::parse_response(unit, function, self.frame.adu()?)?;
+        if is_write {
+            master::check_write_echo(&self.request, data)?;
+        }
+        Ok(data)
+    }
+
+    pub async fn read_coils(&mut self, unit: u8, addr: u16, out: &mut [bool]) -> Result<(), Error> {
+        let quantity = quantity(out.len())?;
+        let data = self
+            .request(unit, &Request::ReadCoils { addr, quantity })
+            .await?;
+        master::decode_bits(data, out)
+    }
+
+    pub async fn read_discrete_inputs(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        out: &mut [bool],
+    ) -> Result<(), Error> {
+        let quantity = quantity(out.len())?;
+        let data = self
+            .request(unit, &Request::ReadDiscreteInputs { addr, quantity })
+            .await?;
+        master::decode_bits(data, out)
+    }
+
+    pub async fn read_holding_registers(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        out: &mut [u16],
+    ) -> Result<(), Error> {
+        let quantity = quantity(out.len())?;
+        let data = self
+            .request(unit, &Request::ReadHoldingRegisters { addr, quantity })
+            .await?;
+        master::decode_registers(data, out)
+    }
+
+    pub async fn read_input_registers(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        out: &mut [u16],
+    ) -> Result<(), Error> {
+        let quantity = quantity(out.len())?;
+        let data = self
+            .request(unit, &Request::ReadInputRegisters { addr, quantity })
+            .await?;
+        master::decode_registers(data, out)
+    }
+
+    pub async fn write_single_coil(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        value: bool,
+    ) -> Result<(), Error> {
+        self.request(unit, &Request::WriteSingleCoil { addr, value })
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn write_single_register(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        value: u16,
+    ) -> Result<(), Error> {
+        self.request(unit, &Request::WriteSingleRegister { addr, value })
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn write_multiple_coils(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        values: &[bool],
+    ) -> Result<(), Error> {
+        self.request(unit, &Request::WriteMultipleCoils { addr, values })
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn write_multiple_registers(
+        &mut self,
+        unit: u8,
+        addr: u16,
+        values: &[u16],
+    ) -> Result<(), Error> {
+        self.request(unit, &Request::WriteMultipleRegisters { addr, values })
+            .await
+            .map(|_| ())
+    }
+}
+
+/// 读取数量转换为 u16，超出范围的由编码时检查
+#[cfg(feature = "embassy")]
+fn quantity(len: usize) -> Result<u16, Error> {
+    u16::try_from(len).map_err(|_| Error::InvalidRequest)
+}
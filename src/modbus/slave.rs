@@ -0,0 +1,448 @@
+//! 从机请求处理，不依赖硬件
+
+use super::frame::{append_crc, check_crc};
+use super::{Exception, FunctionCode, MAX_ADU_LEN};
+
+/// 读线圈和离散输入的最大数量
+const MAX_READ_BITS: u16 = 2000;
+/// 读寄存器的最大数量
+const MAX_READ_REGISTERS: u16 = 125;
+/// 写多个线圈的最大数量
+const MAX_WRITE_BITS: u16 = 1968;
+/// 写多个寄存器的最大数量
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+/// 从机的数据模型，由用户实现
+///
+/// 每次只访问一个地址，没有实现的方法返回 [`Exception::IllegalFunction`]。
+/// 写多个线圈或寄存器时逐个调用写方法，中途出错时之前的写入不会撤销
+pub trait Handler {
+    fn read_coil(&mut self, _addr: u16) -> Result<bool, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+
+    fn read_discrete_input(&mut self, _addr: u16) -> Result<bool, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+
+    fn read_holding_register(&mut self, _addr: u16) -> Result<u16, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+
+    fn read_input_register(&mut self, _addr: u16) -> Result<u16, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+
+    fn write_coil(&mut self, _addr: u16, _value: bool) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+
+    fn write_register(&mut self, _addr: u16, _value: u16) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+}
+
+/// 处理一帧请求，`request` 是包含 CRC 的完整 ADU
+///
+/// 返回需要发送的响应长度，响应写在 `response` 中。CRC 错误、地址不是本机的帧以及广播帧
+/// 不需要响应，返回 `None`
+pub fn handle_request<H: Handler + ?Sized>(
+    unit_id: u8,
+    handler: &mut H,
+    request: &[u8],
+    response: &mut [u8; MAX_ADU_LEN],
+) -> Option<usize> {
+    let request = check_crc(request).ok()?;
+    let (unit, function, pdu) = (request[0], request[1], &request[2..]);
+    if unit != unit_id && unit != 0 {
+        return None;
+    }
+
+    let rst = dispatch(handler, function, pdu, &mut response[2..MAX_ADU_LEN - 2]);
+    // 广播帧只执行不响应
+    if unit == 0 {
+        return None;
+    }
+
+    response[0] = unit;
+    let len = match rst {
+        Ok(len) => {
+            response[1] = function;
+            2 + len
+        }
+        Err(exception) => {
+            response[1] = function | 0x80;
+            response[2] = exception as u8;
+            3
+        }
+    };
+    Some(append_crc(response, len))
+}
+
+fn read_u16(data: &[u8], index: usize) -> u16 {
+    u16::from_be_bytes([data[index], data[index + 1]])
+}
+
+/// 检查起始地址和数量，返回数量
+fn check_range(addr: u16, quantity: u16, max: u16) -> Result<u16, Exception> {
+    if quantity == 0 || quantity > max {
+        return Err(Exception::IllegalDataValue);
+    }
+    if addr as u32 + quantity as u32 > 0x10000 {
+        return Err(Exception::IllegalDataAddress);
+    }
+    Ok(quantity)
+}
+
+/// 执行请求，返回写在 `out` 中的数据长度（不包括地址和功能码）
+fn dispatch<H: Handler + ?Sized>(
+    handler: &mut H,
+    function: u8,
+    pdu: &[u8],
+    out: &mut [u8],
+) -> Result<usize, Exception> {
+    let function = FunctionCode::from_u8(function).ok_or(Exception::IllegalFunction)?;
+
+    // 所有支持的功能码都以起始地址和数量（或数值）开头
+    if pdu.len() < 4 {
+        return Err(Exception::IllegalDataValue);
+    }
+    let addr = read_u16(pdu, 0);
+    let value = read_u16(pdu, 2);
+
+    match function {
+        FunctionCode::ReadCoils | FunctionCode::ReadDiscreteInputs => {
+            let quantity = check_range(addr, value, MAX_READ_BITS)?;
+            let bytes = quantity.div_ceil(8) as usize;
+            out[0] = bytes as u8;
+            out[1..1 + bytes].fill(0);
+            for i in 0..quantity {
+                let bit = if function == FunctionCode::ReadCoils {
+                    handler.read_coil(addr + i)?
+                } else {
+                    handler.read_discrete_input(addr + i)?
+                };
+                out[1 + i as usize / 8] |= (bit as u8) << (i % 8);
+            }
+            Ok(1 + bytes)
+        }
+        FunctionCode::ReadHoldingRegisters | FunctionCode::ReadInputRegisters => {
+            let quantity = check_range(addr, value, MAX_READ_REGISTERS)?;
+            out[0] = (quantity * 2) as u8;
+            for i in 0..quantity {
+                let v = if function == FunctionCode::ReadHoldingRegisters {
+                    handler.read_holding_register(addr + i)?
+                } else {
+                    handler.read_input_register(addr + i)?
+                };
+                let index = 1 + i as usize * 2;
+                out[index..index + 2].copy_from_slice(&v.to_be_bytes());
+            }
+            Ok(1 + quantity as usize * 2)
+        }
+        FunctionCode::WriteSingleCoil => {
+            let bit = match value {
+                0xff00 => true,
+                0x0000 => false,
+                _ => return Err(Exception::IllegalDataValue),
+            };
+            handler.write_coil(addr, bit)?;
+            out[..4].copy_from_slice(&pdu[..4]);
+            Ok(4)
+        }
+        FunctionCode::WriteSingleRegister => {
+            handler.write_register(addr, value)?;
+            out[..4].copy_from_slice(&pdu[..4]);
+            Ok(4)
+        }
+        FunctionCode::WriteMultipleCoils => {
+            let quantity = check_range(addr, value, MAX_WRITE_BITS)?;
+            let bytes = quantity.div_ceil(8) as usize;
+            if pdu.len() != 5 + bytes || pdu[4] as usize != bytes {
+                return Err(Exception::IllegalDataValue);
+            }
+            for i in 0..quantity {
+                let bit = pdu[5 + i as usize / 8] & (1 << (i % 8)) != 0;
+                handler.write_coil(addr + i, bit)?;
+            }
+            out[..4].copy_from_slice(&pdu[..4]);
+            Ok(4)
+        }
+        FunctionCode::WriteMultipleRegisters => {
+            let quantity = check_range(addr, value, MAX_WRITE_REGISTERS)?;
+            let bytes = quantity as usize * 2;
+            if pdu.len() != 5 + bytes || pdu[4] as usize != bytes {
+                return Err(Exception::IllegalDataValue);
+            }
+            for i in 0..quantity {
+                handler.write_register(addr + i, read_u16(pdu, 5 + i as usize * 2))?;
+            }
+            out[..4].copy_from_slice(&pdu[..4]);
+            Ok(4)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Registers {
+        coils: [bool; 16],
+        discrete: [bool; 16],
+        holding: [u16; 8],
+        input: [u16; 8],
+    }
+
+    impl Registers {
+        fn new() -> Self {
+            let mut discrete = [false; 16];
+            discrete[0] = true;
+            discrete[9] = true;
+            Self {
+                coils: [false; 16],
+                discrete,
+                holding: [0x1234, 0x5678, 0, 0, 0, 0, 0, 0],
+                input: [0xabcd, 0, 0, 0, 0, 0, 0, 0x00ff],
+            }
+        }
+    }
+
+    impl Handler for Registers {
+        fn read_coil(&mut self, addr: u16) -> Result<bool, Exception> {
+            self.coils
+                .get(addr as usize)
+                .copied()
+                .ok_or(Exception::IllegalDataAddress)
+        }
+
+        fn read_discrete_input(&mut self, addr: u16) -> Result<bool, Exception> {
+            self.discrete
+                .get(addr as usize)
+                .copied()
+                .ok_or(Exception::IllegalDataAddress)
+        }
+
+        fn read_holding_register(&mut self, addr: u16) -> Result<u16, Exception> {
+            self.holding
+                .get(addr as usize)
+                .copied()
+                .ok_or(Exception::IllegalDataAddress)
+        }
+
+        fn read_input_register(&mut self, addr: u16) -> Result<u16, Exception> {
+            self.input
+                .get(addr as usize)
+                .copied()
+                .ok_or(Exception::IllegalDataAddress)
+        }
+
+        fn write_coil(&mut self, addr: u16, value: bool) -> Result<(), Exception> {
+            let v = self
+                .coils
+                .get_mut(addr as usize)
+                .ok_or(Exception::IllegalDataAddress)?;
+            *v = value;
+            Ok(())
+        }
+
+        fn write_register(&mut self, addr: u16, value: u16) -> Result<(), Exception> {
+            let v = self
+                .holding
+                .get_mut(addr as usize)
+                .ok_or(Exception::IllegalDataAddress)?;
+            *v = value;
+            Ok(())
+        }
+    }
+
+    /// 没有实现任何方法的从机
+    struct Empty;
+
+    impl Handler for Empty {}
+
+    /// 处理请求，`request` 不包括 CRC，返回不包括 CRC 的响应
+    fn handle<H: Handler>(
+        unit_id: u8,
+        handler: &mut H,
+        request: &[u8],
+    ) -> Option<([u8; MAX_ADU_LEN], usize)> {
+        let mut adu = [0; MAX_ADU_LEN];
+        adu[..request.len()].copy_from_slice(request);
+        let len = append_crc(&mut adu, request.len());
+
+        let mut response = [0; MAX_ADU_LEN];
+        let len = handle_request(unit_id, handler, &adu[..len], &mut response)?;
+        let data_len = check_crc(&response[..len]).unwrap().len();
+        Some((response, data_len))
+    }
+
+    fn assert_response<H: Handler>(handler: &mut H, request: &[u8], expected: &[u8]) {
+        let (response, len) = handle(1, handler, request).unwrap();
+        assert_eq!(&response[..len], expected);
+    }
+
+    #[test]
+    fn read_coils() {
+        let mut regs = Registers::new();
+        regs.coils[1] = true;
+        regs.coils[8] = true;
+        assert_response(
+            &mut regs,
+            &[1, 0x01, 0x00, 0x00, 0x00, 0x0a],
+            &[1, 0x01, 2, 0x02, 0x01],
+        );
+    }
+
+    #[test]
+    fn read_discrete_inputs() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x02, 0x00, 0x00, 0x00, 0x10],
+            &[1, 0x02, 2, 0x01, 0x02],
+        );
+    }
+
+    #[test]
+    fn read_holding_registers() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x03, 0x00, 0x00, 0x00, 0x02],
+            &[1, 0x03, 4, 0x12, 0x34, 0x56, 0x78],
+        );
+    }
+
+    #[test]
+    fn read_input_registers() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x04, 0x00, 0x07, 0x00, 0x01],
+            &[1, 0x04, 2, 0x00, 0xff],
+        );
+    }
+
+    #[test]
+    fn write_single_coil() {
+        let mut regs = Registers::new();
+        let request = [1, 0x05, 0x00, 0x03, 0xff, 0x00];
+        assert_response(&mut regs, &request, &request);
+        assert!(regs.coils[3]);
+
+        let request = [1, 0x05, 0x00, 0x03, 0x00, 0x00];
+        assert_response(&mut regs, &request, &request);
+        assert!(!regs.coils[3]);
+
+        // 只允许 0xff00 和 0x0000
+        assert_response(
+            &mut regs,
+            &[1, 0x05, 0x00, 0x03, 0x12, 0x34],
+            &[1, 0x85, 0x03],
+        );
+    }
+
+    #[test]
+    fn write_single_register() {
+        let mut regs = Registers::new();
+        let request = [1, 0x06, 0x00, 0x02, 0xbe, 0xef];
+        assert_response(&mut regs, &request, &request);
+        assert_eq!(regs.holding[2], 0xbeef);
+    }
+
+    #[test]
+    fn write_multiple_coils() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x0f, 0x00, 0x02, 0x00, 0x0a, 2, 0x05, 0x02],
+            &[1, 0x0f, 0x00, 0x02, 0x00, 0x0a],
+        );
+        let expected = [
+            false, false, true, false, true, false, false, false, false, false, false, true,
+        ];
+        assert_eq!(regs.coils[..12], expected);
+
+        // 字节数与数量不符
+        assert_response(
+            &mut regs,
+            &[1, 0x0f, 0x00, 0x02, 0x00, 0x0a, 1, 0x05],
+            &[1, 0x8f, 0x03],
+        );
+    }
+
+    #[test]
+    fn write_multiple_registers() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x10, 0x00, 0x06, 0x00, 0x02, 4, 0x00, 0x0a, 0x01, 0x02],
+            &[1, 0x10, 0x00, 0x06, 0x00, 0x02],
+        );
+        assert_eq!(regs.holding[6..], [0x000a, 0x0102]);
+    }
+
+    #[test]
+    fn illegal_data_address() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x03, 0x00, 0x07, 0x00, 0x02],
+            &[1, 0x83, 0x02],
+        );
+        // 起始地址加数量超过 0x10000
+        assert_response(
+            &mut regs,
+            &[1, 0x01, 0xff, 0xff, 0x00, 0x02],
+            &[1, 0x81, 0x02],
+        );
+        // 数量为 0 或超过上限
+        assert_response(
+            &mut regs,
+            &[1, 0x03, 0x00, 0x00, 0x00, 0x00],
+            &[1, 0x83, 0x03],
+        );
+        assert_response(
+            &mut regs,
+            &[1, 0x04, 0x00, 0x00, 0x00, 126],
+            &[1, 0x84, 0x03],
+        );
+    }
+
+    #[test]
+    fn illegal_function() {
+        let mut regs = Registers::new();
+        assert_response(
+            &mut regs,
+            &[1, 0x07, 0x00, 0x00, 0x00, 0x01],
+            &[1, 0x87, 0x01],
+        );
+        assert_response(
+            &mut Empty,
+            &[1, 0x03, 0x00, 0x00, 0x00, 0x01],
+            &[1, 0x83, 0x01],
+        );
+    }
+
+    #[test]
+    fn broadcast_and_filter() {
+        let mut regs = Registers::new();
+
+        // 广播帧执行但不响应
+        assert!(handle(1, &mut regs, &[0, 0x06, 0x00, 0x01, 0x00, 0x07]).is_none());
+        assert_eq!(regs.holding[1], 7);
+
+        // 其他从机的帧被忽略
+        assert!(handle(1, &mut regs, &[2, 0x06, 0x00, 0x01, 0x00, 0x08]).is_none());
+        assert_eq!(regs.holding[1], 7);
+
+        // CRC 错误的帧被忽略
+        let mut adu = [1, 0x06, 0x00, 0x01, 0x00, 0x09, 0, 0];
+        append_crc(&mut adu, 6);
+        adu[7] ^= 0xff;
+        let mut response = [0; MAX_ADU_LEN];
+        assert_eq!(handle_request(1, &mut regs, &adu, &mut response), None);
+        assert_eq!(regs.holding[1], 7);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::usart;
+
+/// 最大的 ADU 长度
+pub const MAX_ADU_LEN: usize = 256;
+
+/// 功能码
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FunctionCode {
+    ReadCoils = 0x01,
+    ReadDiscreteInputs = 0x02,
+    ReadHoldingRegisters = 0x03,
+    ReadInputRegisters = 0x04,
+    WriteSingleCoil = 0x05,
+    WriteSingleRegister = 0x06,
+    WriteMultipleCoils = 0x0f,
+    WriteMultipleRegisters = 0x10,
+}
+
+impl FunctionCode {
+    pub fn from_u8(code: u8) -> Option<Self> {
+        Some(match code {
+            0x01 => Self::ReadCoils,
+            0x02 => Self::ReadDiscreteInputs,
+            0x03 => Self::ReadHoldingRegisters,
+            0x04 => Self::ReadInputRegisters,
+            0x05 => Self::WriteSingleCoil,
+            0x06 => Self::WriteSingleRegister,
+            0x0f => Self::WriteMultipleCoils,
+            0x10 => Self::WriteMultipleRegisters,
+            _ => return None,
+        })
+    }
+}
+
+/// 异常码
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exception {
+    /// 不支持的功能码
+    IllegalFunction = 0x01,
+    /// 地址超出范围
+    IllegalDataAddress = 0x02,
+    /// 数量或数值不合法
+    IllegalDataValue = 0x03,
+    /// 设备内部错误
+    ServerDeviceFailure = 0x04,
+}
+
+impl Exception {
+    pub fn from_u8(code: u8) -> Option<Self> {
+        Some(match code {
+            0x01 => Self::IllegalFunction,
+            0x02 => Self::IllegalDataAddress,
+            0x03 => Self::IllegalDataValue,
+            0x04 => Self::ServerDeviceFailure,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// 串口错误
+    Usart(usart::Error),
+    /// 等待响应超时
+    Timeout,
+    /// 帧太短或者 CRC 错误
+    Crc,
+    /// 帧超过了 [`MAX_ADU_LEN`]
+    Overflow,
+    /// 请求的数量或长度不合法
+    InvalidRequest,
+    /// 响应与请求不匹配
+    InvalidResponse,
+    /// 从机返回了异常响应，未知的异常码为 `None`
+    Exception(Option<Exception>),
+}
+
+impl From<usart::Error> for Error {
+    fn from(value: usart::Error) -> Self {
+        Self::Usart(value)
+    }
+}
@@ -0,0 +1,94 @@
+//! 内置命令
+
+use super::{parse_u32, Command, CommandError, Output};
+use crate::clock;
+use core::fmt::Write;
+
+/// 一次最多读取的字数
+const MAX_PEEK_WORDS: u32 = 64;
+
+pub(super) static BUILTINS: [Command; 4] = [
+    Command {
+        name: "peek",
+        usage: "<addr> [count]",
+        help: "读取寄存器或内存，按字对齐",
+        handler: peek,
+    },
+    Command {
+        name: "poke",
+        usage: "<addr> <value>",
+        help: "写入寄存器或内存，按字对齐",
+        handler: poke,
+    },
+    Command {
+        name: "clocks",
+        usage: "",
+        help: "显示系统时钟",
+        handler: clocks,
+    },
+    Command {
+        name: "reboot",
+        usage: "",
+        help: "软件复位",
+        handler: reboot,
+    },
+];
+
+fn word_addr(arg: &str) -> Result<u32, CommandError> {
+    let addr = parse_u32(arg)?;
+    if addr % 4 != 0 {
+        return Err(CommandError::InvalidArgument);
+    }
+    Ok(addr)
+}
+
+fn peek(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+    let (addr, count) = match args {
+        [addr] => (word_addr(addr)?, 1),
+        [addr, count] => (word_addr(addr)?, parse_u32(count)?),
+        _ => return Err(CommandError::Usage),
+    };
+    if count == 0 || count > MAX_PEEK_WORDS {
+        return Err(CommandError::InvalidArgument);
+    }
+
+    for i in 0..count {
+        let addr = addr
+            .checked_add(i * 4)
+            .ok_or(CommandError::InvalidArgument)?;
+        // 访问不存在的地址会进入 HardFault，由使用者保证地址有效
+        let v = unsafe { core::ptr::read_volatile(addr as *const u32) };
+        let _ = writeln!(out, "0x{:08x}: 0x{:08x}", addr, v);
+    }
+    Ok(())
+}
+
+fn poke(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+    let [addr, value] = args else {
+        return Err(CommandError::Usage);
+    };
+    let addr = word_addr(addr)?;
+    let value = parse_u32(value)?;
+
+    unsafe { core::ptr::write_volatile(addr as *mut u32, value) };
+    let v = unsafe { core::ptr::read_volatile(addr as *const u32) };
+    let _ = writeln!(out, "0x{:08x}: 0x{:08x}", addr, v);
+    Ok(())
+}
+
+fn clocks(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+    if !args.is_empty() {
+        return Err(CommandError::Usage);
+    }
+    let _ = writeln!(out, "sysclk: {} Hz", clock::sys_core_clock());
+    let _ = writeln!(out, "hclk:   {} Hz", clock::sys_hclk());
+    let _ = writeln!(out, "pclk:   {} Hz", clock::sys_pclk());
+    Ok(())
+}
+
+fn reboot(_out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+    if !args.is_empty() {
+        return Err(CommandError::Usage);
+    }
+    cortex_m::peripheral::SCB::sys_reset()
+}
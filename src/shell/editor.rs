@@ -0,0 +1,475 @@
+//! 行编辑和历史记录，不依赖硬件
+
+use super::Output;
+use core::fmt::Write;
+
+/// 固定缓冲区中的历史记录，每条记录以 0 结尾，空间不足时丢弃最旧的记录
+pub struct History<const H: usize> {
+    buf: [u8; H],
+    len: usize,
+}
+
+impl<const H: usize> History<H> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; H],
+            len: 0,
+        }
+    }
+
+    /// 添加一条记录，与最新的记录相同时忽略
+    pub fn push(&mut self, line: &[u8]) {
+        if line.is_empty() || line.len() >= H || self.get(0) == Some(line) {
+            return;
+        }
+
+        while self.len + line.len() + 1 > H {
+            // 丢弃最旧的一条
+            let end = self.buf[..self.len]
+                .iter()
+                .position(|v| *v == 0)
+                .unwrap_or(0);
+            self.buf.copy_within(end + 1..self.len, 0);
+            self.len -= end + 1;
+        }
+
+        self.buf[self.len..self.len + line.len()].copy_from_slice(line);
+        self.buf[self.len + line.len()] = 0;
+        self.len += line.len() + 1;
+    }
+
+    /// 返回第 `index` 新的记录，0 为最新的
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        if self.len == 0 {
+            return None;
+        }
+        // 最后一个 0 之后是空的
+        self.buf[..self.len - 1].rsplit(|v| *v == 0).nth(index)
+    }
+}
+
+impl<const H: usize> Default for History<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 转义序列的解析状态
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// 收到了 ESC
+    Esc,
+    /// 收到了 `ESC [` 或 `ESC O`，以及其后的数字参数
+    Csi(u8),
+}
+
+/// 行编辑器，`N` 为一行的最大长度，`H` 为历史记录缓冲区的长度
+///
+/// 支持退格、Delete、左右方向键、Home/End 移动光标和插入，上下方向键浏览历史记录，
+/// Ctrl-C 放弃当前行。回显和光标移动使用 VT100 转义序列
+pub struct LineEditor<const N: usize, const H: usize> {
+    line: [u8; N],
+    len: usize,
+    cursor: usize,
+    history: History<H>,
+    /// 正在浏览的历史记录
+    browse: Option<usize>,
+    escape: Escape,
+    /// 上一个字节是 CR，忽略紧跟的 LF
+    last_cr: bool,
+}
+
+impl<const N: usize, const H: usize> Default for LineEditor<N, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const H: usize> LineEditor<N, H> {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; N],
+            len: 0,
+            cursor: 0,
+            history: History::new(),
+            browse: None,
+            escape: Escape::None,
+            last_cr: false,
+        }
+    }
+
+    /// 当前正在编辑的内容
+    pub fn line(&self) -> &[u8] {
+        &self.line[..self.len]
+    }
+
+    pub fn history(&self) -> &History<H> {
+        &self.history
+    }
+
+    /// 取出输入完成的一行并清空编辑器，返回的内容可以原地修改
+    pub fn take_line(&mut self) -> &mut [u8] {
+        let len = self.len;
+        self.len = 0;
+        self.cursor = 0;
+        &mut self.line[..len]
+    }
+
+    /// 处理一个输入的字节，回显写在 `out` 中，输入完成一行时返回 true
+    pub fn feed(&mut self, byte: u8, out: &mut Output) -> bool {
+        let last_cr = core::mem::replace(&mut self.last_cr, byte == b'\r');
+
+        match self.escape {
+            Escape::Esc => {
+                self.escape = match byte {
+                    b'[' | b'O' => Escape::Csi(0),
+                    _ => Escape::None,
+                };
+                return false;
+            }
+            Escape::Csi(param) => {
+                if byte.is_ascii_digit() {
+                    self.escape = Escape::Csi(param.saturating_mul(10).saturating_add(byte - b'0'));
+                } else {
+                    self.escape = Escape::None;
+                    self.on_escape(byte, param, out);
+                }
+                return false;
+            }
+            Escape::None => {}
+        }
+
+        match byte {
+            b'\r' | b'\n' => {
+                if byte == b'\n' && last_cr {
+                    return false;
+                }
+                out.push(b"\r\n");
+                self.history.push(&self.line[..self.len]);
+                self.browse = None;
+                return true;
+            }
+            // Ctrl-C
+            0x03 => {
+                out.push(b"^C\r\n");
+                self.len = 0;
+                self.cursor = 0;
+                self.browse = None;
+                return true;
+            }
+            0x08 | 0x7f if self.cursor > 0 => {
+                self.cursor -= 1;
+                out.push(b"\x08");
+                self.remove(out);
+            }
+            0x1b => self.escape = Escape::Esc,
+            0x20..=0x7e => self.insert(byte, out),
+            _ => {}
+        }
+        false
+    }
+
+    /// 处理 CSI 转义序列
+    fn on_escape(&mut self, code: u8, param: u8, out: &mut Output) {
+        match (code, param) {
+            (b'A', _) => {
+                let index = self.browse.map_or(0, |i| i + 1);
+                if self.history.get(index).is_some() {
+                    self.browse = Some(index);
+                    self.load_history(out);
+                }
+            }
+            (b'B', _) => match self.browse {
+                Some(0) | None => {
+                    self.browse = None;
+                    self.replace(&[], out);
+                }
+                Some(i) => {
+                    self.browse = Some(i - 1);
+                    self.load_history(out);
+                }
+            },
+            (b'C', _) if self.cursor < self.len => {
+                self.cursor += 1;
+                out.push(b"\x1b[C");
+            }
+            (b'D', _) if self.cursor > 0 => {
+                self.cursor -= 1;
+                out.push(b"\x1b[D");
+            }
+            (b'H', _) | (b'~', 1) => self.move_to(0, out),
+            (b'F', _) | (b'~', 4) => self.move_to(self.len, out),
+            (b'~', 3) if self.cursor < self.len => self.remove(out),
+            _ => {}
+        }
+    }
+
+    /// 在光标处插入，重绘光标之后的内容
+    fn insert(&mut self, byte: u8, out: &mut Output) {
+        if self.len == N {
+            // 满了，响铃
+            out.push(b"\x07");
+            return;
+        }
+
+        self.line
+            .copy_within(self.cursor..self.len, self.cursor + 1);
+        self.line[self.cursor] = byte;
+        self.len += 1;
+        out.push(&self.line[self.cursor..self.len]);
+        self.cursor += 1;
+        Self::back(self.len - self.cursor, out);
+    }
+
+    /// 删除光标处的字符，重绘光标之后的内容
+    fn remove(&mut self, out: &mut Output) {
+        self.line
+            .copy_within(self.cursor + 1..self.len, self.cursor);
+        self.len -= 1;
+        out.push(&self.line[self.cursor..self.len]);
+        out.push(b" ");
+        Self::back(self.len - self.cursor + 1, out);
+    }
+
+    fn load_history(&mut self, out: &mut Output) {
+        let mut line = [0; N];
+        let len = match self.browse.and_then(|i| self.history.get(i)) {
+            Some(v) => {
+                line[..v.len()].copy_from_slice(v);
+                v.len()
+            }
+            None => 0,
+        };
+        self.replace(&line[..len], out);
+    }
+
+    /// 用 `line` 替换当前的内容，光标移到行尾
+    fn replace(&mut self, line: &[u8], out: &mut Output) {
+        self.move_to(0, out);
+        out.push(b"\x1b[K");
+        self.line[..line.len()].copy_from_slice(line);
+        self.len = line.len();
+        self.cursor = line.len();
+        out.push(line);
+    }
+
+    fn move_to(&mut self, cursor: usize, out: &mut Output) {
+        if cursor < self.cursor {
+            Self::back(self.cursor - cursor, out);
+        } else if cursor > self.cursor {
+            let _ = write!(out, "\x1b[{}C", cursor - self.cursor);
+        }
+        self.cursor = cursor;
+    }
+
+    /// 光标左移 `n` 列
+    fn back(n: usize, out: &mut Output) {
+        if n > 0 {
+            let _ = write!(out, "\x1b[{}D", n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    /// 依次输入 `input`，返回回显和最后一个字节是否完成了一行
+    fn feed<const N: usize, const H: usize>(
+        editor: &mut LineEditor<N, H>,
+        input: &[u8],
+    ) -> (Vec<u8>, bool) {
+        let mut buf = [0; 256];
+        let mut out = Output::new(&mut buf);
+        let mut done = false;
+        for v in input {
+            done = editor.feed(*v, &mut out);
+        }
+        (out.as_bytes().to_vec(), done)
+    }
+
+    #[test]
+    fn history_eviction() {
+        let mut history = History::<10>::new();
+        assert_eq!(history.get(0), None);
+
+        history.push(b"abc");
+        history.push(b"def");
+        assert_eq!(history.get(0), Some(&b"def"[..]));
+        assert_eq!(history.get(1), Some(&b"abc"[..]));
+
+        // 空间不足，丢弃最旧的一条
+        history.push(b"gh");
+        assert_eq!(history.get(0), Some(&b"gh"[..]));
+        assert_eq!(history.get(1), Some(&b"def"[..]));
+        assert_eq!(history.get(2), None);
+
+        // 需要丢弃多条
+        history.push(b"123456");
+        assert_eq!(history.get(0), Some(&b"123456"[..]));
+        assert_eq!(history.get(1), Some(&b"gh"[..]));
+        assert_eq!(history.get(2), None);
+
+        history.push(b"123456789");
+        assert_eq!(history.get(0), Some(&b"123456789"[..]));
+        assert_eq!(history.get(1), None);
+    }
+
+    #[test]
+    fn history_ignore() {
+        let mut history = History::<10>::new();
+        history.push(b"abc");
+        // 与最新的记录相同
+        history.push(b"abc");
+        assert_eq!(history.get(1), None);
+        // 空行和放不下的行
+        history.push(b"");
+        history.push(b"0123456789");
+        assert_eq!(history.get(0), Some(&b"abc"[..]));
+        assert_eq!(history.get(1), None);
+    }
+
+    #[test]
+    fn enter() {
+        let mut editor = LineEditor::<16, 64>::new();
+        assert_eq!(feed(&mut editor, b"ls"), (b"ls".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\r"), (b"\r\n".to_vec(), true));
+        assert_eq!(editor.take_line(), b"ls");
+        assert_eq!(editor.history().get(0), Some(&b"ls"[..]));
+
+        // CR LF 只算一次
+        assert_eq!(feed(&mut editor, b"\n"), (Vec::new(), false));
+        assert_eq!(feed(&mut editor, b"\n"), (b"\r\n".to_vec(), true));
+        assert_eq!(editor.take_line(), b"");
+
+        // 不可见字符被忽略
+        assert_eq!(feed(&mut editor, b"a\x01\tb"), (b"ab".to_vec(), false));
+        assert_eq!(editor.line(), b"ab");
+    }
+
+    #[test]
+    fn cursor_and_insert() {
+        let mut editor = LineEditor::<16, 64>::new();
+        feed(&mut editor, b"ac");
+        assert_eq!(feed(&mut editor, b"\x1b[D"), (b"\x1b[D".to_vec(), false));
+        // 插入后重绘光标之后的内容，光标回到插入的位置之后
+        assert_eq!(feed(&mut editor, b"b"), (b"bc\x1b[1D".to_vec(), false));
+        assert_eq!(editor.line(), b"abc");
+
+        // 已经在行尾，右移无效
+        assert_eq!(
+            feed(&mut editor, b"\x1b[C\x1b[C"),
+            (b"\x1b[C".to_vec(), false)
+        );
+
+        // Home 和 End，包括 `ESC [ 1 ~`、`ESC O H` 等形式
+        assert_eq!(feed(&mut editor, b"\x1b[H"), (b"\x1b[3D".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\x1b[D"), (Vec::new(), false));
+        assert_eq!(feed(&mut editor, b"\x1b[F"), (b"\x1b[3C".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\x1b[1~"), (b"\x1b[3D".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\x1bOF"), (b"\x1b[3C".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\x1bOH"), (b"\x1b[3D".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"\x1b[4~"), (b"\x1b[3C".to_vec(), false));
+    }
+
+    #[test]
+    fn backspace_and_delete() {
+        let mut editor = LineEditor::<16, 64>::new();
+        feed(&mut editor, b"abcd\x1b[D\x1b[D");
+
+        // 删除光标前的 b，重绘之后的内容并擦除最后一列
+        assert_eq!(
+            feed(&mut editor, b"\x08"),
+            (b"\x08cd \x1b[3D".to_vec(), false)
+        );
+        assert_eq!(editor.line(), b"acd");
+        assert_eq!(
+            feed(&mut editor, b"\x7f"),
+            (b"\x08cd \x1b[3D".to_vec(), false)
+        );
+        assert_eq!(editor.line(), b"cd");
+        // 已经在行首
+        assert_eq!(feed(&mut editor, b"\x7f"), (Vec::new(), false));
+
+        // Delete 删除光标处的字符
+        assert_eq!(
+            feed(&mut editor, b"\x1b[3~"),
+            (b"d \x1b[2D".to_vec(), false)
+        );
+        assert_eq!(editor.line(), b"d");
+        feed(&mut editor, b"\x1b[F");
+        assert_eq!(feed(&mut editor, b"\x1b[3~"), (Vec::new(), false));
+        assert_eq!(editor.line(), b"d");
+    }
+
+    #[test]
+    fn escape() {
+        let mut editor = LineEditor::<16, 64>::new();
+        // 不支持的转义序列被忽略，不会插入字符
+        assert_eq!(
+            feed(&mut editor, b"\x1b[2J\x1b[15~\x1bx"),
+            (Vec::new(), false)
+        );
+        assert_eq!(editor.line(), b"");
+        assert_eq!(feed(&mut editor, b"y"), (b"y".to_vec(), false));
+    }
+
+    #[test]
+    fn full_line() {
+        let mut editor = LineEditor::<4, 64>::new();
+        assert_eq!(feed(&mut editor, b"abcd"), (b"abcd".to_vec(), false));
+        assert_eq!(feed(&mut editor, b"e"), (b"\x07".to_vec(), false));
+        assert_eq!(editor.line(), b"abcd");
+    }
+
+    #[test]
+    fn ctrl_c() {
+        let mut editor = LineEditor::<16, 64>::new();
+        feed(&mut editor, b"abc");
+        assert_eq!(feed(&mut editor, b"\x03"), (b"^C\r\n".to_vec(), true));
+        assert_eq!(editor.take_line(), b"");
+        assert_eq!(editor.history().get(0), None);
+    }
+
+    #[test]
+    fn browse_history() {
+        let mut editor = LineEditor::<16, 64>::new();
+        for line in [&b"one\r"[..], b"two\r"] {
+            feed(&mut editor, line);
+            editor.take_line();
+        }
+
+        feed(&mut editor, b"x");
+        assert_eq!(
+            feed(&mut editor, b"\x1b[A"),
+            (b"\x1b[1D\x1b[Ktwo".to_vec(), false)
+        );
+        assert_eq!(
+            feed(&mut editor, b"\x1b[A"),
+            (b"\x1b[3D\x1b[Kone".to_vec(), false)
+        );
+        // 已经是最旧的记录
+        assert_eq!(feed(&mut editor, b"\x1b[A"), (Vec::new(), false));
+        assert_eq!(editor.line(), b"one");
+
+        assert_eq!(
+            feed(&mut editor, b"\x1b[B"),
+            (b"\x1b[3D\x1b[Ktwo".to_vec(), false)
+        );
+        // 回到空行
+        assert_eq!(
+            feed(&mut editor, b"\x1b[B"),
+            (b"\x1b[3D\x1b[K".to_vec(), false)
+        );
+        assert_eq!(editor.line(), b"");
+
+        // 编辑历史记录后输入
+        feed(&mut editor, b"\x1b[A!\r");
+        assert_eq!(editor.take_line(), b"two!");
+        assert_eq!(editor.history().get(0), Some(&b"two!"[..]));
+    }
+}
@@ -0,0 +1,275 @@
+//! 串口命令行
+//!
+//! 在任意 `embedded_io_async` 的读写对象（例如 [`UsartRx`](crate::usart::UsartRx)/
+//! [`UsartTx`](crate::usart::UsartTx) 或 [`BufferedUart`](crate::usart::BufferedUart)）上运行，
+//! 支持行编辑、历史记录和带引号的参数。命令在 `&'static` 的 [`Command`] 表中注册，
+//! 另外有内置的 `help`、`peek`、`poke`、`clocks` 和 `reboot` 命令。
+//!
+//! 命令的处理函数是同步的，输出写入 [`Output`]，处理函数返回后一次性发送，超过缓冲区的部分被丢弃。
+//! 行编辑、分词和命令分发都不依赖硬件，可以在主机上测试。
+//!
+//! ```rust, ignore
+//! fn led(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+//!     match args {
+//!         ["on"] => writeln!(out, "led on").ok(),
+//!         ["off"] => writeln!(out, "led off").ok(),
+//!         _ => return Err(CommandError::Usage),
+//!     };
+//!     Ok(())
+//! }
+//!
+//! static COMMANDS: [Command; 1] = [Command {
+//!     name: "led",
+//!     usage: "on|off",
+//!     help: "控制 LED",
+//!     handler: led,
+//! }];
+//!
+//! let mut shell = Shell::<64, 256, 256>::new(&COMMANDS, "> ");
+//! let (mut rx, mut tx) = usart.split();
+//! shell.run(&mut rx, &mut tx).await;
+//! ```
+
+mod builtin;
+mod editor;
+mod tokenize;
+
+pub use editor::{History, LineEditor};
+pub use tokenize::{tokenize, TokenizeError};
+
+use core::convert::Infallible;
+use core::fmt::Write;
+use embedded_io_async::{Error as _, Read};
+
+/// 一条命令最多的参数数量，包括命令名
+pub const MAX_ARGS: usize = 16;
+
+/// 命令处理函数，`args` 不包括命令名
+pub type Handler = fn(out: &mut Output, args: &[&str]) -> Result<(), CommandError>;
+
+/// 命令错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandError {
+    /// 参数数量错误，显示命令的用法
+    Usage,
+    /// 参数的值错误
+    InvalidArgument,
+    /// 执行失败
+    Failed,
+}
+
+/// 命令
+pub struct Command {
+    pub name: &'static str,
+    /// 参数的说明，显示在 `help` 中
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub handler: Handler,
+}
+
+/// 解析十进制或者以 `0x` 开头的十六进制数
+pub fn parse_u32(s: &str) -> Result<u32, CommandError> {
+    let rst = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    rst.map_err(|_| CommandError::InvalidArgument)
+}
+
+/// 命令的输出缓冲区，`\n` 被转换为 `\r\n`，超过缓冲区的部分被丢弃
+pub struct Output<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Output<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    /// 原样写入，不转换换行
+    pub fn push(&mut self, data: &[u8]) {
+        let cnt = data.len().min(self.buf.len() - self.len);
+        self.buf[self.len..self.len + cnt].copy_from_slice(&data[..cnt]);
+        self.len += cnt;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl Write for Output<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for (i, line) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.push(b"\r\n");
+            }
+            self.push(line.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+/// 分词并执行一行命令，先查找 `commands`，再查找内置命令
+pub fn execute(commands: &[Command], line: &mut [u8], out: &mut Output) {
+    let mut args = [""; MAX_ARGS];
+    let cnt = match tokenize(line, &mut args) {
+        Ok(0) => return,
+        Ok(cnt) => cnt,
+        Err(e) => {
+            let _ = writeln!(out, "error: {:?}", e);
+            return;
+        }
+    };
+    let (name, args) = (args[0], &args[1..cnt]);
+
+    if name == "help" {
+        for command in commands.iter().chain(&builtin::BUILTINS) {
+            let _ = writeln!(
+                out,
+                "{:<8} {:<16} {}",
+                command.name, command.usage, command.help
+            );
+        }
+        return;
+    }
+
+    let Some(command) = commands
+        .iter()
+        .chain(&builtin::BUILTINS)
+        .find(|c| c.name == name)
+    else {
+        let _ = writeln!(out, "unknown command: {}", name);
+        return;
+    };
+
+    let _ = match (command.handler)(out, args) {
+        Ok(()) => Ok(()),
+        Err(CommandError::Usage) => writeln!(out, "usage: {} {}", command.name, command.usage),
+        Err(e) => writeln!(out, "error: {:?}", e),
+    };
+}
+
+/// 命令行，`N` 为一行的最大长度，`H` 为历史记录缓冲区的长度，`O` 为输出缓冲区的长度
+pub struct Shell<const N: usize, const H: usize, const O: usize> {
+    commands: &'static [Command],
+    prompt: &'static str,
+    editor: LineEditor<N, H>,
+    out: [u8; O],
+}
+
+impl<const N: usize, const H: usize, const O: usize> Shell<N, H, O> {
+    pub fn new(commands: &'static [Command], prompt: &'static str) -> Self {
+        // 输出缓冲区需要容纳一整行的回显和光标移动
+        assert!(O >= N + 16);
+        Self {
+            commands,
+            prompt,
+            editor: LineEditor::new(),
+            out: [0; O],
+        }
+    }
+
+    /// 一直运行，只在读写出错时返回
+    pub async fn run<R: Read, W: embedded_io_async::Write>(
+        &mut self,
+        rx: &mut R,
+        tx: &mut W,
+    ) -> Result<Infallible, embedded_io::ErrorKind> {
+        let mut buf = [0; 16];
+        tx.write_all(self.prompt.as_bytes())
+            .await
+            .map_err(|e| e.kind())?;
+
+        loop {
+            let cnt = rx.read(&mut buf).await.map_err(|e| e.kind())?;
+            for v in &buf[..cnt] {
+                let mut out = Output::new(&mut self.out);
+                let done = self.editor.feed(*v, &mut out);
+                if done {
+                    execute(self.commands, self.editor.take_line(), &mut out);
+                }
+                tx.write_all(out.as_bytes()).await.map_err(|e| e.kind())?;
+                if done {
+                    tx.write_all(self.prompt.as_bytes())
+                        .await
+                        .map_err(|e| e.kind())?;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(out: &mut Output, args: &[&str]) -> Result<(), CommandError> {
+        match args {
+            [] => Err(CommandError::Usage),
+            ["fail"] => Err(CommandError::Failed),
+            _ => {
+                for (i, arg) in args.iter().enumerate() {
+                    let _ = write!(out, "{}{}", if i > 0 { "," } else { "" }, arg);
+                }
+                let _ = writeln!(out);
+                Ok(())
+            }
+        }
+    }
+
+    static COMMANDS: [Command; 1] = [Command {
+        name: "echo",
+        usage: "<text>...",
+        help: "回显参数",
+        handler: echo,
+    }];
+
+    /// 执行一行命令，检查输出
+    fn run(line: &str, check: impl FnOnce(&[u8])) {
+        let mut line_buf = [0; 64];
+        let line_buf = &mut line_buf[..line.len()];
+        line_buf.copy_from_slice(line.as_bytes());
+        let mut buf = [0; 512];
+        let mut out = Output::new(&mut buf);
+        execute(&COMMANDS, line_buf, &mut out);
+        check(out.as_bytes());
+    }
+
+    #[test]
+    fn dispatch() {
+        run("echo a 'b c'", |out| assert_eq!(out, b"a,b c\r\n"));
+        run("  ", |out| assert_eq!(out, b""));
+        run("echo", |out| assert_eq!(out, b"usage: echo <text>...\r\n"));
+        run("echo fail", |out| assert_eq!(out, b"error: Failed\r\n"));
+        run("nope", |out| assert_eq!(out, b"unknown command: nope\r\n"));
+        run("echo 'a", |out| {
+            assert_eq!(out, b"error: UnclosedQuote\r\n")
+        });
+        run("help", |out| {
+            assert!(out.starts_with(b"echo     <text>...        "));
+            assert!(out.windows(6).any(|v| v == b"\r\npeek"));
+        });
+    }
+
+    #[test]
+    fn output() {
+        let mut buf = [0; 8];
+        let mut out = Output::new(&mut buf);
+        let _ = write!(out, "a\nb");
+        assert_eq!(out.as_bytes(), b"a\r\nb");
+        // 超过缓冲区的部分被丢弃
+        out.push(b"0123456789");
+        assert_eq!(out.as_bytes(), b"a\r\nb0123");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_u32("1234"), Ok(1234));
+        assert_eq!(parse_u32("0x20000000"), Ok(0x2000_0000));
+        assert_eq!(parse_u32("0XfF"), Ok(0xff));
+        assert_eq!(parse_u32("0x"), Err(CommandError::InvalidArgument));
+        assert_eq!(parse_u32("-1"), Err(CommandError::InvalidArgument));
+    }
+}
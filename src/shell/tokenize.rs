@@ -0,0 +1,155 @@
+//! 命令行分词，不依赖硬件
+
+use super::MAX_ARGS;
+
+/// 分词错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizeError {
+    /// 引号没有闭合
+    UnclosedQuote,
+    /// 参数数量超过上限
+    TooManyArgs,
+    /// 参数不是合法的 UTF-8
+    Utf8,
+}
+
+/// 原地分词，返回参数的数量，参数写在 `args` 中
+///
+/// 参数以空格分隔，单引号或双引号中的空格不分隔参数，引号外和双引号中的 `\` 转义下一个字符，
+/// 单引号中的内容原样保留。去掉引号和转义后的参数写回 `line` 中，不需要额外的缓冲区
+pub fn tokenize<'a>(line: &'a mut [u8], args: &mut [&'a str]) -> Result<usize, TokenizeError> {
+    let len = line.len();
+    let mut read = 0;
+    let mut write = 0;
+    // 每个参数在 line 中的范围
+    let mut ranges = [(0, 0); MAX_ARGS];
+    let max = args.len().min(ranges.len());
+    let mut cnt = 0;
+
+    loop {
+        while read < len && line[read] == b' ' {
+            read += 1;
+        }
+        if read == len {
+            break;
+        }
+        if cnt == max {
+            return Err(TokenizeError::TooManyArgs);
+        }
+
+        let start = write;
+        let mut quote = None;
+        while read < len {
+            let v = line[read];
+            read += 1;
+            match (quote, v) {
+                (None, b' ') => break,
+                (None, b'"' | b'\'') => quote = Some(v),
+                (Some(q), v) if q == v => quote = None,
+                (None | Some(b'"'), b'\\') if read < len => {
+                    line[write] = line[read];
+                    write += 1;
+                    read += 1;
+                }
+                _ => {
+                    line[write] = v;
+                    write += 1;
+                }
+            }
+        }
+        if quote.is_some() {
+            return Err(TokenizeError::UnclosedQuote);
+        }
+        ranges[cnt] = (start, write);
+        cnt += 1;
+    }
+
+    let line: &'a [u8] = line;
+    for (arg, (start, end)) in args.iter_mut().zip(&ranges[..cnt]) {
+        *arg = core::str::from_utf8(&line[*start..*end]).map_err(|_| TokenizeError::Utf8)?;
+    }
+    Ok(cnt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 用 `n` 个参数的空间分词，检查分词的结果
+    fn check_with(line: &str, n: usize, expected: Result<&[&str], TokenizeError>) {
+        let mut buf = [0; 64];
+        let buf = &mut buf[..line.len()];
+        buf.copy_from_slice(line.as_bytes());
+        let mut args = [""; MAX_ARGS + 1];
+        let args = &mut args[..n];
+        let rst = tokenize(buf, args).map(|cnt| &args[..cnt]);
+        assert_eq!(rst, expected, "line {:?}", line);
+    }
+
+    fn check(line: &str, expected: &[&str]) {
+        check_with(line, MAX_ARGS, Ok(expected));
+    }
+
+    #[test]
+    fn spaces() {
+        check("", &[]);
+        check("   ", &[]);
+        check("peek 0x20000000", &["peek", "0x20000000"]);
+        check("  a   b  c ", &["a", "b", "c"]);
+    }
+
+    #[test]
+    fn quotes() {
+        check(r#"echo "a b" 'c d'"#, &["echo", "a b", "c d"]);
+        check(r#"echo a"b c"d"#, &["echo", "ab cd"]);
+        check(r#"echo "" ''"#, &["echo", "", ""]);
+        check(
+            r#"echo "it's" 'say "hi"'"#,
+            &["echo", "it's", r#"say "hi""#],
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        check(r"echo a\ b", &["echo", "a b"]);
+        check(r#"echo "a\"b" \'"#, &["echo", "a\"b", "'"]);
+        // 单引号中的 \ 原样保留
+        check(r"echo 'a\b'", &["echo", r"a\b"]);
+        // 行尾的 \ 原样保留
+        check(r"echo a\", &["echo", r"a\"]);
+    }
+
+    #[test]
+    fn errors() {
+        check_with(r#"echo "a b"#, MAX_ARGS, Err(TokenizeError::UnclosedQuote));
+        check_with("echo 'a", MAX_ARGS, Err(TokenizeError::UnclosedQuote));
+
+        let mut buf = [0xff, b' ', b'a'];
+        let mut args = [""; 2];
+        assert_eq!(tokenize(&mut buf, &mut args), Err(TokenizeError::Utf8));
+    }
+
+    #[test]
+    fn too_many_args() {
+        let args = [
+            "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p",
+        ];
+        check_with("a b c d e f g h i j k l m n o p", MAX_ARGS, Ok(&args));
+        check_with(
+            "a b c d e f g h i j k l m n o p q",
+            MAX_ARGS,
+            Err(TokenizeError::TooManyArgs),
+        );
+
+        // `args` 比 MAX_ARGS 长时也不超过 MAX_ARGS
+        check_with(
+            "a b c d e f g h i j k l m n o p q",
+            MAX_ARGS + 1,
+            Err(TokenizeError::TooManyArgs),
+        );
+
+        // 受 `args` 的长度限制，行尾的空格不算参数
+        check_with("a b c", 2, Err(TokenizeError::TooManyArgs));
+        check_with("  a b  ", 2, Ok(&["a", "b"]));
+    }
+}
@@ -0,0 +1,414 @@
+//! 中断驱动的缓冲串口
+//!
+//! 接收和发送各使用一个用户提供的静态环形缓冲区，由串口中断直接搬运数据，
+//! 只有在缓冲区过半、接收空闲、发送缓冲区清空或发生错误时才唤醒任务，
+//! 高波特率下其他任务运行期间也不会丢失数据。
+//!
+//! 缓冲模式下发送不处理 RS-485 的驱动使能，也不支持单线半双工模式。
+//!
+//! ```rust, ignore
+//! static mut RX_BUF: [u8; 128] = [0; 128];
+//! static mut TX_BUF: [u8; 64] = [0; 64];
+//! let usart = AnyUsart::new(p.USART1, Some(rx), Some(tx), None, None, Default::default());
+//! let mut uart = BufferedUart::new(usart, unsafe { &mut RX_BUF }, unsafe { &mut TX_BUF });
+//! let n = uart.read(&mut buf).await?;
+//! uart.write_all(&buf[..n]).await?;
+//! ```
+
+use super::{AnyUsart, Error, Event, Id, Instance, UsartRx, UsartTx};
+use crate::mode::Async;
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::task::Poll;
+use critical_section::Mutex;
+use embassy_hal_internal::atomic_ring_buffer::RingBuffer;
+use embassy_sync::waitqueue::AtomicWaker;
+use enumset::EnumSet;
+
+/// 每个串口缓冲模式的共享状态，任务和中断之间通过它传递数据
+struct State {
+    rx_buf: RingBuffer,
+    tx_buf: RingBuffer,
+    rx_waker: AtomicWaker,
+    tx_waker: AtomicWaker,
+    /// 尚未报告的接收错误
+    error: Mutex<Cell<Option<Error>>>,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            rx_buf: RingBuffer::new(),
+            tx_buf: RingBuffer::new(),
+            rx_waker: AtomicWaker::new(),
+            tx_waker: AtomicWaker::new(),
+            error: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    fn take_error(&self) -> Option<Error> {
+        critical_section::with(|cs| self.error.borrow(cs).take())
+    }
+
+    fn set_error(&self, error: Error) {
+        critical_section::with(|cs| self.error.borrow(cs).set(Some(error)));
+    }
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const _STATE: State = State::new();
+const _STATE_COUNT: usize = Id::USART2 as usize + 1;
+static STATES: [State; _STATE_COUNT] = [_STATE; _STATE_COUNT];
+
+#[inline]
+fn state<T: Instance>() -> &'static State {
+    &STATES[T::id() as usize]
+}
+
+/// 在任务中开关中断事件，避免和中断函数同时修改控制寄存器
+fn event_config<T: Instance>(events: EnumSet<Event>, en: bool) {
+    critical_section::with(|_| events.iter().for_each(|e| T::event_config(e, en)));
+}
+
+/// 中断函数调用，缓冲模式未启用时返回 `false`
+pub(super) unsafe fn on_interrupt<T: Instance>() -> bool {
+    let state = state::<T>();
+    // 注意环形缓冲区的 `len` 是容量，为 0 表示没有初始化
+    let (rx_len, tx_len) = (state.rx_buf.len(), state.tx_buf.len());
+    if rx_len == 0 && tx_len == 0 {
+        return false;
+    }
+
+    // 接收
+    if T::is_event_enable(Event::RXNE) {
+        let error =
+            T::event_flag(Event::PE) || T::event_flag(Event::FE) || T::event_flag(Event::NE);
+
+        if error {
+            // 出错的数据被丢弃
+            if let Err(error) = T::check_error() {
+                state.set_error(error);
+            }
+            state.rx_waker.wake();
+        } else if T::event_flag(Event::RXNE) || T::event_flag(Event::ORE) {
+            if T::event_flag(Event::ORE) {
+                // 之前的数据丢失了，数据寄存器中的数据仍然有效
+                state.set_error(Error::Overrun);
+                state.rx_waker.wake();
+            }
+
+            let mut writer = state.rx_buf.writer();
+            writer.push_one(T::read());
+
+            let free: usize = writer.push_bufs().iter().map(|(_, len)| len).sum();
+            if free == 0 {
+                // 缓冲区满，暂停接收中断，数据读走后再开启
+                T::event_config(Event::RXNE, false);
+                T::event_config(Event::IDLE, false);
+                state.rx_waker.wake();
+            } else if free <= rx_len / 2 {
+                state.rx_waker.wake();
+            }
+        }
+    }
+
+    if T::is_event_enable(Event::IDLE) && T::event_flag(Event::IDLE) {
+        T::event_clear(Event::IDLE);
+        state.rx_waker.wake();
+    }
+
+    // 发送
+    if T::is_event_enable(Event::TXE) && T::event_flag(Event::TXE) {
+        let mut reader = state.tx_buf.reader();
+        match reader.pop_one() {
+            Some(v) => {
+                T::write(v);
+                if state.tx_buf.is_empty() {
+                    // 提前通知任务补充数据，最后一个字节发送期间可以继续写入
+                    state.tx_waker.wake();
+                }
+            }
+            None => T::event_config(Event::TXE, false),
+        }
+    }
+
+    if T::is_event_enable(Event::TC) && T::event_flag(Event::TC) {
+        T::event_config(Event::TC, false);
+        state.tx_waker.wake();
+    }
+
+    true
+}
+
+/// 缓冲串口
+pub struct BufferedUart<'d, T: Instance> {
+    rx: BufferedUartRx<'d, T>,
+    tx: BufferedUartTx<'d, T>,
+}
+
+/// 缓冲串口的接收部分
+pub struct BufferedUartRx<'d, T: Instance> {
+    _rx: UsartRx<'d, T, Async>,
+}
+
+/// 缓冲串口的发送部分
+pub struct BufferedUartTx<'d, T: Instance> {
+    _tx: UsartTx<'d, T, Async>,
+}
+
+impl<'d, T: Instance> BufferedUart<'d, T> {
+    /// 新建缓冲串口，`rx_buf` 和 `tx_buf` 分别作为接收和发送的环形缓冲区
+    pub fn new(
+        usart: AnyUsart<'d, T, Async>,
+        rx_buf: &'static mut [u8],
+        tx_buf: &'static mut [u8],
+    ) -> Self {
+        let (rx, tx) = usart.split();
+        Self {
+            rx: BufferedUartRx::new(rx, rx_buf),
+            tx: BufferedUartTx::new(tx, tx_buf),
+        }
+    }
+
+    pub fn split(self) -> (BufferedUartRx<'d, T>, BufferedUartTx<'d, T>) {
+        (self.rx, self.tx)
+    }
+}
+
+impl<'d, T: Instance> BufferedUartRx<'d, T> {
+    /// 新建缓冲接收对象
+    pub fn new(rx: UsartRx<'d, T, Async>, buf: &'static mut [u8]) -> Self {
+        assert!(!buf.is_empty());
+
+        event_config::<T>(Event::RXNE | Event::IDLE, false);
+        unsafe { state::<T>().rx_buf.init(buf.as_mut_ptr(), buf.len()) };
+        state::<T>().take_error();
+
+        // 清除之前的标志
+        (Event::IDLE | Event::NE | Event::FE | Event::PE | Event::ORE)
+            .iter()
+            .for_each(|e| T::event_clear(e));
+        event_config::<T>(Event::RXNE | Event::IDLE, true);
+
+        Self { _rx: rx }
+    }
+
+    /// 读取数据，缓冲区为空时等待
+    ///
+    /// 缓冲区中的数据读完之后才报告期间发生的接收错误
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let data = self.fill_buf().await?;
+        let cnt = data.len().min(buf.len());
+        buf[..cnt].copy_from_slice(&data[..cnt]);
+        self.consume(cnt);
+
+        Ok(cnt)
+    }
+
+    /// 等待并返回缓冲区中连续的一段数据，需要调用 [`Self::consume`] 将其消耗
+    pub async fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        let state = state::<T>();
+        let (ptr, len) = poll_fn(|cx| {
+            state.rx_waker.register(cx.waker());
+
+            let mut reader = unsafe { state.rx_buf.reader() };
+            let (ptr, len) = reader.pop_buf();
+            if len > 0 {
+                return Poll::Ready(Ok((ptr, len)));
+            }
+
+            match state.take_error() {
+                Some(error) => Poll::Ready(Err(error)),
+                None => Poll::Pending,
+            }
+        })
+        .await?;
+
+        Ok(unsafe { core::slice::from_raw_parts(ptr, len) })
+    }
+
+    /// 消耗 `amt` 个数据
+    pub fn consume(&mut self, amt: usize) {
+        let mut reader = unsafe { state::<T>().rx_buf.reader() };
+        reader.pop_done(amt);
+
+        // 缓冲区满时中断会被关闭，腾出空间后重新开启
+        event_config::<T>(Event::RXNE | Event::IDLE, true);
+    }
+
+    /// 缓冲区中是否有数据或者错误待读取
+    pub fn read_ready(&self) -> bool {
+        let state = state::<T>();
+        !state.rx_buf.is_empty()
+            || critical_section::with(|cs| state.error.borrow(cs).get()).is_some()
+    }
+}
+
+impl<'d, T: Instance> Drop for BufferedUartRx<'d, T> {
+    fn drop(&mut self) {
+        event_config::<T>(Event::RXNE | Event::IDLE, false);
+        unsafe { state::<T>().rx_buf.deinit() };
+    }
+}
+
+impl<'d, T: Instance> BufferedUartTx<'d, T> {
+    /// 新建缓冲发送对象
+    pub fn new(tx: UsartTx<'d, T, Async>, buf: &'static mut [u8]) -> Self {
+        assert!(!buf.is_empty());
+
+        event_config::<T>(Event::TXE | Event::TC, false);
+        unsafe { state::<T>().tx_buf.init(buf.as_mut_ptr(), buf.len()) };
+
+        Self { _tx: tx }
+    }
+
+    /// 把数据写入缓冲区，缓冲区满时等待，返回写入的数量
+    pub async fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let state = state::<T>();
+        poll_fn(|cx| {
+            state.tx_waker.register(cx.waker());
+
+            let mut writer = unsafe { state.tx_buf.writer() };
+            let data = writer.push_slice();
+            if data.is_empty() {
+                return Poll::Pending;
+            }
+
+            let cnt = data.len().min(buf.len());
+            data[..cnt].copy_from_slice(&buf[..cnt]);
+            writer.push_done(cnt);
+
+            event_config::<T>(EnumSet::empty() | Event::TXE, true);
+            Poll::Ready(Ok(cnt))
+        })
+        .await
+    }
+
+    /// 等待缓冲区中的数据全部发送完毕
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        let state = state::<T>();
+        poll_fn(|cx| {
+            state.tx_waker.register(cx.waker());
+
+            if !state.tx_buf.is_empty() {
+                return Poll::Pending;
+            }
+
+            if !T::event_flag(Event::TC) {
+                event_config::<T>(EnumSet::empty() | Event::TC, true);
+                return Poll::Pending;
+            }
+
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+
+    /// 缓冲区是否还有空间
+    pub fn write_ready(&self) -> bool {
+        !state::<T>().tx_buf.is_full()
+    }
+}
+
+impl<'d, T: Instance> Drop for BufferedUartTx<'d, T> {
+    fn drop(&mut self) {
+        event_config::<T>(Event::TXE | Event::TC, false);
+        unsafe { state::<T>().tx_buf.deinit() };
+    }
+}
+
+impl<'d, T: Instance> embedded_io::ErrorType for BufferedUart<'d, T> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_io::ErrorType for BufferedUartRx<'d, T> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_io::ErrorType for BufferedUartTx<'d, T> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_io_async::Read for BufferedUart<'d, T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.rx.read(buf).await
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::Read for BufferedUartRx<'d, T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        BufferedUartRx::read(self, buf).await
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::BufRead for BufferedUart<'d, T> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        self.rx.fill_buf().await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.rx.consume(amt)
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::BufRead for BufferedUartRx<'d, T> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+        BufferedUartRx::fill_buf(self).await
+    }
+
+    fn consume(&mut self, amt: usize) {
+        BufferedUartRx::consume(self, amt)
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::Write for BufferedUart<'d, T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.tx.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.tx.flush().await
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::Write for BufferedUartTx<'d, T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        BufferedUartTx::write(self, buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        BufferedUartTx::flush(self).await
+    }
+}
+
+impl<'d, T: Instance> embedded_io::ReadReady for BufferedUart<'d, T> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.rx.read_ready())
+    }
+}
+
+impl<'d, T: Instance> embedded_io::ReadReady for BufferedUartRx<'d, T> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(BufferedUartRx::read_ready(self))
+    }
+}
+
+impl<'d, T: Instance> embedded_io::WriteReady for BufferedUart<'d, T> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.tx.write_ready())
+    }
+}
+
+impl<'d, T: Instance> embedded_io::WriteReady for BufferedUartTx<'d, T> {
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(BufferedUartTx::write_ready(self))
+    }
+}
@@ -0,0 +1,150 @@
+//! 通过串口输出 defmt 日志
+//!
+//! 开启 `defmt-uart` 特性后注册 `defmt::global_logger`，调用 [`init`] 指定用来输出的串口。
+//! 日志编码后先放入缓冲区，每条日志结束时在临界区内一次性发送，创建串口时提供了 tx dma
+//! 通道则使用 dma 发送。每条日志都是同步发送完的，panic 时不会有日志残留在缓冲区中，
+//! `defmt::flush()` 还会等待最后一个字节离开移位寄存器。
+//!
+//! ```rust, ignore
+//! static TX: StaticCell<UsartTx<'static, USART1, Blocking>> = StaticCell::new();
+//! let usart = AnyUsart::new(p.USART1, None, Some(tx), None, Some(channel1), Default::default());
+//! let (_, tx) = usart.split();
+//! usart::defmt_logger::init(TX.init(tx));
+//! defmt::info!("hello");
+//! ```
+
+use super::{Instance, UsartTx};
+use crate::mode::Blocking;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use critical_section::RestoreState;
+
+/// 日志缓冲区的长度
+const BUF_LEN: usize = 128;
+
+/// 擦除了串口类型的发送接口
+trait LogWriter {
+    fn write(&mut self, bytes: &[u8]);
+    fn flush(&mut self);
+}
+
+impl<T: Instance> LogWriter for UsartTx<'static, T, Blocking> {
+    fn write(&mut self, bytes: &[u8]) {
+        // 日志输出不能失败，忽略错误
+        let _ = UsartTx::<T, Blocking>::write(self, bytes);
+    }
+
+    fn flush(&mut self) {
+        T::tx_flush();
+    }
+}
+
+/// 编码后的日志缓冲区
+struct LogBuffer {
+    buf: [u8; BUF_LEN],
+    len: usize,
+    writer: Option<&'static mut dyn LogWriter>,
+}
+
+impl LogBuffer {
+    fn push(&mut self, bytes: &[u8]) {
+        for v in bytes {
+            if self.len == BUF_LEN {
+                self.send();
+            }
+            self.buf[self.len] = *v;
+            self.len += 1;
+        }
+    }
+
+    /// 发送缓冲区中的数据，还没有初始化时直接丢弃
+    fn send(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            writer.write(&self.buf[..self.len]);
+        }
+        self.len = 0;
+    }
+}
+
+struct State {
+    encoder: defmt::Encoder,
+    restore: RestoreState,
+    buffer: LogBuffer,
+}
+
+/// 只在临界区中访问
+struct StateCell(UnsafeCell<State>);
+
+unsafe impl Sync for StateCell {}
+
+static STATE: StateCell = StateCell(UnsafeCell::new(State {
+    encoder: defmt::Encoder::new(),
+    restore: RestoreState::invalid(),
+    buffer: LogBuffer {
+        buf: [0; BUF_LEN],
+        len: 0,
+        writer: None,
+    },
+}));
+
+/// 日志是否已经被占用
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// 指定输出日志的串口，之前的日志被丢弃
+pub fn init<T: Instance>(tx: &'static mut UsartTx<'static, T, Blocking>) {
+    critical_section::with(|_| {
+        let state = unsafe { &mut *STATE.0.get() };
+        state.buffer.len = 0;
+        state.buffer.writer = Some(tx);
+    });
+}
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        let restore = unsafe { critical_section::acquire() };
+
+        if TAKEN.load(Ordering::Relaxed) {
+            panic!("defmt logger taken reentrantly")
+        }
+        TAKEN.store(true, Ordering::Relaxed);
+
+        let State {
+            encoder,
+            restore: saved,
+            buffer,
+        } = unsafe { &mut *STATE.0.get() };
+        *saved = restore;
+        encoder.start_frame(|bytes| buffer.push(bytes));
+    }
+
+    unsafe fn flush() {
+        let buffer = &mut (*STATE.0.get()).buffer;
+        buffer.send();
+        if let Some(writer) = &mut buffer.writer {
+            writer.flush();
+        }
+    }
+
+    unsafe fn release() {
+        let State {
+            encoder,
+            restore,
+            buffer,
+        } = &mut *STATE.0.get();
+        encoder.end_frame(|bytes| buffer.push(bytes));
+        buffer.send();
+
+        TAKEN.store(false, Ordering::Relaxed);
+        critical_section::release(*restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        let State {
+            encoder, buffer, ..
+        } = &mut *STATE.0.get();
+        encoder.write(bytes, |bytes| buffer.push(bytes));
+    }
+}
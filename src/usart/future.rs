@@ -1,7 +1,11 @@
 use super::{Event, Id, Instance};
 use crate::mcu::peripherals::{USART1, USART2};
 use crate::pac::interrupt;
-use core::{future::Future, marker::PhantomData, task::Poll};
+use core::{
+    future::Future,
+    marker::PhantomData,
+    task::{Poll, Waker},
+};
 use critical_section::CriticalSection;
 
 #[cfg(feature = "embassy")]
@@ -14,10 +18,13 @@ const _ATOMIC_WAKER: AtomicWaker = AtomicWaker::new();
 #[cfg(feature = "embassy")]
 const _EVENT_COUNT: usize = Event::PE as usize + 1;
 #[cfg(feature = "embassy")]
-const _WAKER_COUNT: usize = Id::USART2 as usize;
+const _WAKER_COUNT: usize = Id::USART2 as usize + 1;
+#[allow(clippy::declare_interior_mutable_const)]
+#[cfg(feature = "embassy")]
+const _EVENT_WAKERS: [AtomicWaker; _EVENT_COUNT] = [_ATOMIC_WAKER; _EVENT_COUNT];
 #[cfg(feature = "embassy")]
 pub(super) static EVENT_WAKERS: [[AtomicWaker; _EVENT_COUNT]; _WAKER_COUNT] =
-    [[_ATOMIC_WAKER; _EVENT_COUNT]; _WAKER_COUNT];
+    [_EVENT_WAKERS; _WAKER_COUNT];
 
 pub struct EventFuture<T: Instance> {
     _t: PhantomData<T>,
@@ -48,6 +55,14 @@ impl<T: Instance> EventFuture<T> {
     }
 }
 
+/// 注册事件的唤醒器，并开启事件中断
+pub(super) fn register_waker<T: Instance>(waker: &Waker, events: EnumSet<Event>) {
+    events.iter().for_each(|e| {
+        EVENT_WAKERS[T::id() as usize][e as usize].register(waker);
+        T::event_config(e, true);
+    });
+}
+
 impl<T: Instance> Future for EventFuture<T> {
     type Output = EnumSet<Event>;
     fn poll(
@@ -78,13 +93,19 @@ impl<T: Instance> Future for EventFuture<T> {
 #[interrupt]
 fn USART1() {
     critical_section::with(|cs| unsafe {
-        EventFuture::<USART1>::on_interrupt(cs, Id::USART1 as usize)
+        // 缓冲模式下由缓冲串口接管中断
+        if !super::buffered::on_interrupt::<USART1>() {
+            EventFuture::<USART1>::on_interrupt(cs, Id::USART1 as usize)
+        }
     })
 }
 
 #[interrupt]
 fn USART2() {
     critical_section::with(|cs| unsafe {
-        EventFuture::<USART2>::on_interrupt(cs, Id::USART2 as usize)
+        // 缓冲模式下由缓冲串口接管中断
+        if !super::buffered::on_interrupt::<USART2>() {
+            EventFuture::<USART2>::on_interrupt(cs, Id::USART2 as usize)
+        }
     })
 }
@@ -1,8 +1,6 @@
 pub mod sealed {
     use super::super::*;
     use crate::pac;
-    use core::f32;
-    use num_traits::float::FloatCore;
 
     pub trait Instance {
         fn id() -> Id;
@@ -36,34 +34,35 @@ pub mod sealed {
         }
 
         #[inline]
-        fn read_byte_blocking() -> u8 {
-            while !Self::event_flag(Event::RXNE) {}
+        fn read_byte_blocking() -> Result<u8, Error> {
+            while !Self::event_flag(Event::RXNE) && !Self::event_flag(Event::ORE) {}
 
-            Self::read()
+            Self::check_error()?;
+            Ok(Self::read())
         }
 
         #[inline]
-        fn read_bytes_blocking(buf: &mut [u8]) -> usize {
-            let len = buf.len();
-            for item in buf {
-                *item = Self::read_byte_blocking()
+        fn read_bytes_blocking(buf: &mut [u8]) -> Result<usize, Error> {
+            for item in buf.iter_mut() {
+                *item = Self::read_byte_blocking()?;
             }
-            len
+            Ok(buf.len())
         }
 
-        fn read_bytes_idle_blocking(buf: &mut [u8]) -> usize {
+        fn read_bytes_idle_blocking(buf: &mut [u8]) -> Result<usize, Error> {
             let mut cnt = 0;
             for item in buf {
-                while !Self::event_flag(Event::RXNE) {
+                while !Self::event_flag(Event::RXNE) && !Self::event_flag(Event::ORE) {
                     if Self::event_flag(Event::IDLE) {
-                        *item = Self::read();
-                        return cnt;
+                        Self::event_clear(Event::IDLE);
+                        return Ok(cnt);
                     }
                 }
+                Self::check_error()?;
+                *item = Self::read();
                 cnt += 1;
-                *item = Self::read()
             }
-            cnt
+            Ok(cnt)
         }
 
         #[inline]
@@ -78,6 +77,48 @@ pub mod sealed {
             Self::block().dr.read().dr().bits() as u8
         }
 
+        /// 写入一个 9 位数据
+        #[inline]
+        fn write_word(data: u16) {
+            Self::block()
+                .dr
+                .write(|w| unsafe { w.dr().bits(data & 0x1ff) });
+        }
+
+        /// 读取一个 9 位数据
+        #[inline]
+        fn read_word() -> u16 {
+            Self::block().dr.read().dr().bits()
+        }
+
+        /// 设置本节点的地址，用于地址标记唤醒，只有低 4 位有效
+        #[inline]
+        fn set_node_address(address: u8) {
+            Self::block()
+                .cr2
+                .modify(|_, w| unsafe { w.add().bits(address & 0x0f) });
+        }
+
+        /// 设置静默模式的唤醒方式
+        #[inline]
+        fn set_wake_up_method(method: WakeUpMethod) {
+            Self::block()
+                .cr1
+                .modify(|_, w| w.wake().bit(method == WakeUpMethod::AddressMark));
+        }
+
+        /// 进入或退出静默模式
+        #[inline]
+        fn set_mute(en: bool) {
+            Self::block().cr1.modify(|_, w| w.rwu().bit(en));
+        }
+
+        /// 是否处于静默模式
+        #[inline]
+        fn is_mute() -> bool {
+            Self::block().cr1.read().rwu().bit()
+        }
+
         #[inline]
         fn write_byte_blocking(data: u8) -> Result<(), Error> {
             // txe: 0: 未传输完， 1： 传输完毕
@@ -110,9 +151,17 @@ pub mod sealed {
         }
 
         #[inline]
-        fn set_flow_control(rts: bool, cts: bool) {
+        fn set_flow_control(flow: HwFlowCtrl) {
             let block = Self::block();
-            block.cr3.modify(|_, w| w.ctse().bit(cts).rtse().bit(rts))
+            block
+                .cr3
+                .modify(|_, w| w.ctse().bit(flow.cts()).rtse().bit(flow.rts()))
+        }
+
+        #[inline]
+        fn flow_control() -> HwFlowCtrl {
+            let cr3 = Self::block().cr3.read();
+            HwFlowCtrl::new(cr3.rtse().bit(), cr3.ctse().bit())
         }
 
         #[inline]
@@ -120,6 +169,42 @@ pub mod sealed {
             Self::block().cr1.modify(|_, w| w.re().bit(en))
         }
 
+        /// 配置同步模式的时钟输出，必须在串口停止状态下配置
+        fn set_synchronous(en: bool, config: SyncConfig) {
+            Self::stop();
+            Self::block().cr2.modify(|_, w| {
+                w.clken()
+                    .bit(en)
+                    .cpol()
+                    .bit(config.mode.polarity == embedded_hal::spi::Polarity::IdleHigh)
+                    .cpha()
+                    .bit(config.mode.phase == embedded_hal::spi::Phase::CaptureOnSecondTransition)
+                    .lbcl()
+                    .bit(config.last_bit_clock)
+            });
+            Self::start();
+        }
+
+        /// 单线半双工模式，必须在串口停止状态下配置
+        #[inline]
+        fn set_half_duplex(en: bool) {
+            Self::stop();
+            Self::block().cr3.modify(|_, w| w.hdsel().bit(en));
+            Self::start();
+        }
+
+        #[inline]
+        fn is_rx_enable() -> bool {
+            Self::block().cr1.read().re().bit()
+        }
+
+        /// 数据帧是否有 9 位数据，9 位字长并且没有校验位
+        #[inline]
+        fn is_nine_bit() -> bool {
+            let cr1 = Self::block().cr1.read();
+            cr1.m().bit() && !cr1.pce().bit()
+        }
+
         #[inline]
         fn rx_ready() -> bool {
             Self::block().sr.read().rxne().bit()
@@ -140,6 +225,61 @@ pub mod sealed {
             Self::block().cr3.modify(|_, w| w.ctse().bit(en))
         }
 
+        /// 配置自动波特率检测
+        #[inline]
+        fn auto_baud_rate_config(en: bool, mode: AutoBaudRateMode) {
+            Self::block()
+                .cr3
+                .modify(|_, w| unsafe { w.abrmod().bits(mode as u8).abren().bit(en) });
+        }
+
+        /// 请求重新进行一次自动波特率检测，同时清除 ABRF 和 ABRE
+        #[inline]
+        fn auto_baud_rate_request() {
+            Self::block().sr.modify(|_, w| w.abrrq().set_bit());
+        }
+
+        /// 根据 BRR 和 pclk 计算当前的波特率
+        fn baud_rate() -> u32 {
+            let block = Self::block();
+            let brr = block.brr.read();
+            let mantissa = brr.div_mantissa().bits() as u32;
+            let fraction = brr.div_fraction().bits() as u32;
+            let div = if block.cr3.read().over8().bit() {
+                // 8 倍过采样时，小数部分只有低 3 位有效
+                mantissa * 8 + (fraction & 0x07)
+            } else {
+                mantissa * 16 + fraction
+            };
+
+            clock::sys_pclk().checked_div(div).unwrap_or(0)
+        }
+
+        /// 检查接收错误，发生错误时清除对应的标志并返回错误
+        fn check_error() -> Result<(), Error> {
+            let sr = Self::block().sr.read();
+            let (ore, fe, pe, ne) = (sr.ore().bit(), sr.fe().bit(), sr.pe().bit(), sr.ne().bit());
+            if !(ore || fe || pe || ne) {
+                return Ok(());
+            }
+
+            // 先读 SR 再读 DR，一次清除所有的错误标志，出错的数据被丢弃
+            let data = Self::read_word();
+
+            Err(if ore {
+                Error::Overrun
+            } else if fe && data == 0 {
+                // 数据全为 0 且停止位为低电平，是间隔字符
+                Error::Break
+            } else if fe {
+                Error::Frame
+            } else if pe {
+                Error::Parity
+            } else {
+                Error::Noise
+            })
+        }
+
         /// 清除事件标志
         fn event_clear(event: Event) {
             Self::block().sr.modify(|r, w| match event {
@@ -172,7 +312,7 @@ pub mod sealed {
                 }
                 Event::ABRF => {
                     // ﻿软件通过写 1 到 USART_RQR 寄存器的 ABRRQ位清零该位。
-                    w
+                    w.abrrq().set_bit()
                 }
             });
         }
@@ -208,7 +348,7 @@ pub mod sealed {
                 Event::RXNE => block.cr1.modify(|_, w| w.rxneie().bit(en)),
                 Event::TC => block.cr1.modify(|_, w| w.tcie().bit(en)),
                 Event::TXE => block.cr1.modify(|_, w| w.txeie().bit(en)),
-                Event::CTS => {}
+                Event::CTS => block.cr3.modify(|_, w| w.ctsie().bit(en)),
                 Event::ABRE => {}
                 Event::ABRF => {}
             }
@@ -217,23 +357,40 @@ pub mod sealed {
         /// return event config
         fn is_event_enable(event: Event) -> bool {
             let cr1 = Self::block().cr1.read();
+            let cr3 = Self::block().cr3.read();
             match event {
                 Event::PE => cr1.peie().bit(),
-                Event::FE => false,
-                Event::NE => false,
-                Event::ORE => false,
+                Event::FE | Event::NE | Event::ORE => cr3.eie().bit(),
                 Event::IDLE => cr1.idleie().bit(),
                 Event::RXNE => cr1.rxneie().bit(),
                 Event::TC => cr1.tcie().bit(),
                 Event::TXE => cr1.txeie().bit(),
-                Event::CTS => false,
+                Event::CTS => cr3.ctsie().bit(),
                 Event::ABRE => false,
                 Event::ABRF => false,
             }
         }
 
-        /// 配置串口
-        fn config(config: Config) {
+        /// 配置串口，波特率无法实现或误差超过 `tolerance_ppm` 时不做任何修改
+        fn config(config: &Config, tolerance_ppm: u32) -> Result<BaudRate, Error> {
+            let baud = BaudRate::new(clock::sys_pclk(), config.baud_rate.0, config.over_sampling)?;
+            if !baud.within(tolerance_ppm) {
+                return Err(Error::BaudRate);
+            }
+
+            Self::apply_config(config, baud.brr);
+            Ok(baud)
+        }
+
+        /// 配置串口，不检查波特率，无法实现时使用最接近的分频系数
+        fn config_nearest(config: &Config) {
+            let brr =
+                BaudRate::nearest_brr(clock::sys_pclk(), config.baud_rate.0, config.over_sampling);
+            Self::apply_config(config, brr);
+        }
+
+        /// 写入配置和 BRR
+        fn apply_config(config: &Config, brr: u16) {
             let block = Self::block();
 
             // 必须在串口停止状态下才能重新配置
@@ -273,20 +430,40 @@ pub mod sealed {
                 .modify(|_, w| w.over8().bit(config.over_sampling.into()));
 
             // 设置波特率
-            let over_sampling: u32 = config.over_sampling.div();
-            let baud = config.baud_rate.0;
-            let div: f32 = clock::sys_pclk() as f32 / (baud * over_sampling) as f32;
-            let mantissa: u16 = div.floor() as u16;
-            let fraction: u8 = (16.0 * (div - mantissa as f32)) as u8;
-            block.brr.modify(|_, w| unsafe {
-                w.div_mantissa()
-                    .bits(mantissa)
-                    .div_fraction()
-                    .bits(fraction)
-            });
+            Self::set_brr(brr);
 
             // 开启串口
             Self::start();
         }
+
+        /// 只修改波特率，保持过采样等其他配置
+        fn set_baud_rate(baud: u32, tolerance_ppm: u32) -> Result<BaudRate, Error> {
+            let over_sampling = if Self::block().cr3.read().over8().bit() {
+                OverSampling::Eight
+            } else {
+                OverSampling::Sixteen
+            };
+            let baud = BaudRate::new(clock::sys_pclk(), baud, over_sampling)?;
+            if !baud.within(tolerance_ppm) {
+                return Err(Error::BaudRate);
+            }
+
+            // 必须在串口停止状态下才能修改
+            Self::stop();
+            Self::set_brr(baud.brr);
+            Self::start();
+
+            Ok(baud)
+        }
+
+        #[inline]
+        fn set_brr(brr: u16) {
+            Self::block().brr.write(|w| unsafe {
+                w.div_mantissa()
+                    .bits(brr >> 4)
+                    .div_fraction()
+                    .bits(brr as u8 & 0xf)
+            });
+        }
     }
 }
@@ -0,0 +1,337 @@
+//! LIN 总线
+//!
+//! PY32F030 的串口没有 LIN 模式（没有 LINEN/LBD/LBDL），这里用普通串口模拟：
+//!
+//! - 发送间隔场：临时把波特率降为 9/13，发送一个 0x00，起始位加 8 个数据位正好是 13 位的低电平
+//! - 检测间隔场：正常波特率下收到数据为 0 且带帧错误的字节，只能检测不小于 11 位的间隔场，
+//!   不能选择间隔场的检测长度
+//!
+//! 收发器会把发送的数据回显到接收端，开启 [`LinConfig::echo`] 时逐个字节回读比较，检测总线冲突。
+//!
+//! ```rust, ignore
+//! let usart = AnyUsart::new(p.USART1, Some(rx), Some(tx), None, None, Default::default());
+//! let mut lin = Lin::new(usart, Default::default())?;
+//! // 主机
+//! lin.write_frame(0x10, &[1, 2, 3]).await?;
+//! lin.read_frame(0x20, &mut buf).await?;
+//! // 从机
+//! let id = lin.listen().await?;
+//! if id == 0x20 {
+//!     lin.respond(id, &[4, 5]).await?;
+//! }
+//! ```
+
+use super::Error;
+#[cfg(feature = "embassy")]
+use super::{future::register_waker, AnyUsart, Config, Event, Instance};
+#[cfg(feature = "embassy")]
+use crate::mode::Async;
+#[cfg(feature = "embassy")]
+use core::future::poll_fn;
+#[cfg(feature = "embassy")]
+use core::task::Poll;
+#[cfg(feature = "embassy")]
+use embassy_time::{with_timeout, Duration};
+#[cfg(feature = "embassy")]
+use embedded_time::rate::Extensions;
+#[cfg(feature = "embassy")]
+use enumset::EnumSet;
+
+/// 同步场
+pub const SYNC: u8 = 0x55;
+
+/// 诊断帧的 id，总是使用经典校验和
+const DIAGNOSTIC_IDS: [u8; 2] = [0x3c, 0x3d];
+
+/// LIN 错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinError {
+    /// 串口错误，帧错误、噪声、溢出等
+    Usart(Error),
+    /// id 超出 0 ~ 0x3f
+    Id,
+    /// 受保护 id 的校验位错误
+    Parity,
+    /// 同步场不是 0x55
+    Sync,
+    /// 校验和错误
+    Checksum,
+    /// 回读的数据和发送的不一致，总线冲突
+    Readback,
+    /// 数据场中出现了间隔场
+    Break,
+    /// 响应超时
+    Timeout,
+}
+
+impl From<Error> for LinError {
+    fn from(value: Error) -> Self {
+        Self::Usart(value)
+    }
+}
+
+/// 校验和类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ChecksumType {
+    /// LIN 1.x，只计算数据场
+    Classic,
+    /// LIN 2.x，计算受保护 id 和数据场
+    #[default]
+    Enhanced,
+}
+
+/// 计算受保护 id，`id` 超出 0 ~ 0x3f 时返回 [`LinError::Id`]
+pub fn protected_id(id: u8) -> Result<u8, LinError> {
+    if id > 0x3f {
+        return Err(LinError::Id);
+    }
+
+    let bit = |n: u8| (id >> n) & 1;
+    let p0 = bit(0) ^ bit(1) ^ bit(2) ^ bit(4);
+    let p1 = !(bit(1) ^ bit(3) ^ bit(4) ^ bit(5)) & 1;
+
+    Ok(id | (p0 << 6) | (p1 << 7))
+}
+
+/// 校验受保护 id，返回其中的 id
+pub fn check_pid(pid: u8) -> Result<u8, LinError> {
+    let id = pid & 0x3f;
+    if protected_id(id)? != pid {
+        return Err(LinError::Parity);
+    }
+    Ok(id)
+}
+
+/// 带进位回卷的累加和取反
+fn checksum(init: u8, data: &[u8]) -> u8 {
+    let sum = data.iter().fold(init as u16, |sum, v| {
+        let sum = sum + *v as u16;
+        if sum > 0xff {
+            sum - 0xff
+        } else {
+            sum
+        }
+    });
+    !(sum as u8)
+}
+
+/// 经典校验和
+pub fn classic_checksum(data: &[u8]) -> u8 {
+    checksum(0, data)
+}
+
+/// 增强校验和
+pub fn enhanced_checksum(pid: u8, data: &[u8]) -> u8 {
+    checksum(pid, data)
+}
+
+impl ChecksumType {
+    /// 计算校验和，诊断帧总是使用经典校验和
+    pub fn checksum(&self, pid: u8, data: &[u8]) -> u8 {
+        if *self == Self::Classic || DIAGNOSTIC_IDS.contains(&(pid & 0x3f)) {
+            classic_checksum(data)
+        } else {
+            enhanced_checksum(pid, data)
+        }
+    }
+}
+
+/// LIN 配置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinConfig {
+    /// 波特率，通常为 19200 或 9600
+    pub baud_rate: u32,
+    /// 校验和类型
+    pub checksum: ChecksumType,
+    /// 收发器会回显发送的数据
+    pub echo: bool,
+}
+
+impl Default for LinConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: 19200,
+            checksum: ChecksumType::Enhanced,
+            echo: true,
+        }
+    }
+}
+
+/// 接收到的内容
+#[cfg(feature = "embassy")]
+enum Received {
+    Byte(u8),
+    Break,
+}
+
+/// LIN 节点，同时支持主机和从机的操作
+#[cfg(feature = "embassy")]
+pub struct Lin<'d, T: Instance> {
+    usart: AnyUsart<'d, T, Async>,
+    config: LinConfig,
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> Lin<'d, T> {
+    /// 新建 LIN 节点，串口被重新配置为 8 位数据、无校验、1 位停止位
+    pub fn new(mut usart: AnyUsart<'d, T, Async>, config: LinConfig) -> Result<Self, LinError> {
+        usart.set_config(&Config {
+            baud_rate: config.baud_rate.Bd(),
+            ..Default::default()
+        })?;
+
+        Ok(Self { usart, config })
+    }
+
+    /// 主机发送帧头：间隔场、同步场和受保护 id
+    pub async fn send_header(&mut self, id: u8) -> Result<(), LinError> {
+        let pid = protected_id(id)?;
+        self.send_break().await?;
+        self.write_checked(&[SYNC, pid]).await
+    }
+
+    /// 主机发送一帧数据
+    pub async fn write_frame(&mut self, id: u8, data: &[u8]) -> Result<(), LinError> {
+        self.send_header(id).await?;
+        self.respond(id, data).await
+    }
+
+    /// 主机请求从机的响应，读取 `buf.len()` 个数据
+    pub async fn read_frame(&mut self, id: u8, buf: &mut [u8]) -> Result<(), LinError> {
+        self.send_header(id).await?;
+        self.receive(id, buf).await
+    }
+
+    /// 从机等待帧头，返回 id
+    ///
+    /// 间隔场之前收到的数据被忽略，同步场或受保护 id 错误时返回错误
+    pub async fn listen(&mut self) -> Result<u8, LinError> {
+        loop {
+            if let Ok(Received::Break) = Self::receive_byte().await {
+                break;
+            }
+        }
+
+        // 帧头的最大长度为 34 位的 1.4 倍，间隔场已经占用了 13 位
+        let header = async {
+            match Self::receive_byte().await? {
+                Received::Byte(SYNC) => {}
+                Received::Byte(_) => return Err(LinError::Sync),
+                Received::Break => return Err(LinError::Break),
+            }
+            match Self::receive_byte().await? {
+                Received::Byte(pid) => check_pid(pid),
+                Received::Break => Err(LinError::Break),
+            }
+        };
+        with_timeout(self.bit_time(34 - 13), header)
+            .await
+            .map_err(|_| LinError::Timeout)?
+    }
+
+    /// 发送响应，数据之后自动加上校验和
+    pub async fn respond(&mut self, id: u8, data: &[u8]) -> Result<(), LinError> {
+        let pid = protected_id(id)?;
+        self.write_checked(data).await?;
+        let checksum = self.config.checksum.checksum(pid, data);
+        self.write_checked(&[checksum]).await
+    }
+
+    /// 接收响应并检查校验和，超过 LIN 规定的最大响应时间返回 [`LinError::Timeout`]
+    pub async fn receive(&mut self, id: u8, buf: &mut [u8]) -> Result<(), LinError> {
+        let pid = protected_id(id)?;
+        let timeout = self.bit_time(10 * (buf.len() as u32 + 1));
+
+        let response = async {
+            for v in buf.iter_mut() {
+                *v = Self::receive_data().await?;
+            }
+            Self::receive_data().await
+        };
+        let checksum = with_timeout(timeout, response)
+            .await
+            .map_err(|_| LinError::Timeout)??;
+
+        if checksum != self.config.checksum.checksum(pid, buf) {
+            return Err(LinError::Checksum);
+        }
+        Ok(())
+    }
+
+    /// 返回内部的串口对象
+    pub fn inner(&mut self) -> &mut AnyUsart<'d, T, Async> {
+        &mut self.usart
+    }
+
+    /// 发送 13 位的间隔场
+    async fn send_break(&mut self) -> Result<(), LinError> {
+        // 丢弃之前没有读取的数据
+        T::event_clear(Event::ORE);
+
+        // 降低波特率后，起始位和 8 个数据位正好是 13 位的低电平
+        self.usart.tx.flush().await?;
+        T::set_baud_rate(self.config.baud_rate * 9 / 13, u32::MAX)?;
+        let rst = self.usart.tx.write(&[0]).await;
+        if rst.is_ok() {
+            self.usart.tx.flush().await?;
+        }
+        T::set_baud_rate(self.config.baud_rate, u32::MAX)?;
+        rst?;
+
+        if self.config.echo {
+            // 回显时的波特率与发送时相同，收到的是正常的 0x00
+            match Self::receive_byte().await? {
+                Received::Byte(0) | Received::Break => {}
+                Received::Byte(_) => return Err(LinError::Readback),
+            }
+        }
+        Ok(())
+    }
+
+    /// 逐个字节发送，需要时回读比较
+    async fn write_checked(&mut self, data: &[u8]) -> Result<(), LinError> {
+        for v in data {
+            self.usart.tx.write(core::slice::from_ref(v)).await?;
+            if self.config.echo {
+                match Self::receive_byte().await? {
+                    Received::Byte(echo) if echo == *v => {}
+                    _ => return Err(LinError::Readback),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 接收数据场的一个字节
+    async fn receive_data() -> Result<u8, LinError> {
+        match Self::receive_byte().await? {
+            Received::Byte(v) => Ok(v),
+            Received::Break => Err(LinError::Break),
+        }
+    }
+
+    /// 接收一个字节
+    async fn receive_byte() -> Result<Received, Error> {
+        poll_fn(|cx| {
+            register_waker::<T>(cx.waker(), EnumSet::empty() | Event::RXNE);
+
+            if !T::event_flag(Event::RXNE) && !T::event_flag(Event::ORE) {
+                return Poll::Pending;
+            }
+
+            Poll::Ready(match T::check_error() {
+                Ok(()) => Ok(Received::Byte(T::read())),
+                Err(Error::Break) => Ok(Received::Break),
+                Err(error) => Err(error),
+            })
+        })
+        .await
+    }
+
+    /// `bits` 位的最大允许时间，为标称时间的 1.4 倍
+    fn bit_time(&self, bits: u32) -> Duration {
+        let us = bits as u64 * 1_400_000 / self.config.baud_rate as u64;
+        // 定时器的精度为毫秒，向上取整并多留一个节拍
+        Duration::from_millis(us.div_ceil(1000) + 1)
+    }
+}
@@ -1,7 +1,19 @@
 #[cfg(feature = "embassy")]
+mod buffered;
+#[cfg(feature = "defmt-uart")]
+pub mod defmt_logger;
+#[cfg(feature = "embassy")]
 mod future;
 mod hal;
+pub mod lin;
+mod multiprocessor;
 mod pins;
+#[cfg(feature = "embassy")]
+mod reader;
+#[cfg(feature = "embassy")]
+mod ringbuffered;
+mod rs485;
+mod synchronous;
 mod types;
 
 use crate::clock;
@@ -9,7 +21,7 @@ use crate::clock::peripheral::{
     PeripheralClockIndex, PeripheralIdToClockIndex, PeripheralInterrupt,
 };
 use crate::dma::{self, DmaChannel};
-use crate::gpio::{self, AnyPin};
+use crate::gpio::{self, AnyPin, Output};
 use crate::macro_def::pin_af_for_instance_def;
 use crate::mcu::peripherals::DMA;
 #[cfg(feature = "embassy")]
@@ -17,6 +29,8 @@ use crate::mode::Async;
 use crate::mode::{Blocking, Mode};
 use crate::syscfg::DmaChannelMap;
 #[cfg(feature = "embassy")]
+pub use buffered::{BufferedUart, BufferedUartRx, BufferedUartTx};
+#[cfg(feature = "embassy")]
 use core::future::poll_fn;
 use core::marker::PhantomData;
 #[cfg(feature = "embassy")]
@@ -27,7 +41,13 @@ use enumset::{EnumSet, EnumSetType};
 #[cfg(feature = "embassy")]
 use future::EventFuture;
 use hal::sealed;
-use types::*;
+#[cfg(feature = "embassy")]
+pub use reader::{Lines, ReadError, UartReader};
+#[cfg(feature = "embassy")]
+pub use ringbuffered::RingBufferedUartRx;
+use rs485::DriverEnable;
+pub use synchronous::UsartSync;
+pub use types::*;
 
 pub trait Instance: Peripheral<P = Self> + sealed::Instance + 'static + Send {}
 
@@ -36,6 +56,7 @@ pin_af_for_instance_def!(TxPin, Instance);
 pin_af_for_instance_def!(RxPin, Instance);
 pin_af_for_instance_def!(RtsPin, Instance);
 pin_af_for_instance_def!(CtsPin, Instance);
+pin_af_for_instance_def!(CkPin, Instance);
 
 /// 串口号定义
 #[derive(Clone, Copy, PartialEq)]
@@ -119,6 +140,8 @@ pub struct UsartTx<'d, T: Instance, M: Mode> {
     _cts: Option<PeripheralRef<'d, AnyPin>>,
 
     tx_dma: Option<DmaChannel<'d, DMA, M>>,
+    /// RS-485 收发器的驱动使能
+    de: Option<DriverEnable<'d>>,
 }
 
 /// 串口对象
@@ -132,6 +155,11 @@ impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
         (self.rx, self.tx)
     }
 
+    /// 返回当前的硬件流控配置
+    pub fn flow_control(&self) -> HwFlowCtrl {
+        T::flow_control()
+    }
+
     pub fn new(
         usart: impl Peripheral<P = T> + 'd,
         rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
@@ -142,30 +170,176 @@ impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
 
         config: Config,
     ) -> Self {
-        // 初始化 rxd 引脚
-        let rxd = rxd.map_or_else(
-            || None,
-            |rxd| {
-                into_ref!(rxd);
-                rxd.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::OpenDrain);
-                Some(rxd.map_into())
-            },
-        );
-        // 初始化 txd 引脚
-        let txd = txd.map_or_else(
-            || None,
-            |txd| {
-                into_ref!(txd);
-                txd.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::OpenDrain);
-                Some(txd.map_into())
-            },
-        );
+        let rxd = Self::rxd_pin(rxd);
+        let txd = Self::txd_pin(txd);
 
         into_ref!(usart);
 
         Self::new_inner(usart, rxd, txd, None, None, rx_dma, tx_dma, config)
     }
 
+    /// 新建使用 RTS 和 CTS 硬件流控的串口
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rtscts(
+        usart: impl Peripheral<P = T> + 'd,
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+        txd: Option<impl Peripheral<P = impl TxPin<T>> + 'd>,
+        rts: impl Peripheral<P = impl RtsPin<T>> + 'd,
+        cts: impl Peripheral<P = impl CtsPin<T>> + 'd,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
+        config: Config,
+    ) -> Self {
+        let rxd = Self::rxd_pin(rxd);
+        let txd = Self::txd_pin(txd);
+        let rts = Self::rts_pin(rts);
+        let cts = Self::cts_pin(cts);
+
+        into_ref!(usart);
+
+        Self::new_inner(
+            usart,
+            rxd,
+            txd,
+            Some(cts),
+            Some(rts),
+            rx_dma,
+            tx_dma,
+            config,
+        )
+    }
+
+    /// 新建仅使用 RTS 硬件流控的串口，接收缓冲满时通知对方暂停发送
+    pub fn new_with_rts(
+        usart: impl Peripheral<P = T> + 'd,
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+        txd: Option<impl Peripheral<P = impl TxPin<T>> + 'd>,
+        rts: impl Peripheral<P = impl RtsPin<T>> + 'd,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
+        config: Config,
+    ) -> Self {
+        let rxd = Self::rxd_pin(rxd);
+        let txd = Self::txd_pin(txd);
+        let rts = Self::rts_pin(rts);
+
+        into_ref!(usart);
+
+        Self::new_inner(usart, rxd, txd, None, Some(rts), rx_dma, tx_dma, config)
+    }
+
+    /// 新建仅使用 CTS 硬件流控的串口，对方拉高 CTS 时暂停发送
+    pub fn new_with_cts(
+        usart: impl Peripheral<P = T> + 'd,
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+        txd: Option<impl Peripheral<P = impl TxPin<T>> + 'd>,
+        cts: impl Peripheral<P = impl CtsPin<T>> + 'd,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
+        config: Config,
+    ) -> Self {
+        let rxd = Self::rxd_pin(rxd);
+        let txd = Self::txd_pin(txd);
+        let cts = Self::cts_pin(cts);
+
+        into_ref!(usart);
+
+        Self::new_inner(usart, rxd, txd, Some(cts), None, rx_dma, tx_dma, config)
+    }
+
+    /// 新建 RS-485 模式的串口
+    ///
+    /// 发送前自动使能收发器的驱动器 `de`，发送完成（TC）后释放，
+    /// 保护时间和接收屏蔽由 `rs485` 配置
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_rs485(
+        usart: impl Peripheral<P = T> + 'd,
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+        txd: impl Peripheral<P = impl TxPin<T>> + 'd,
+        de: Output<'d>,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
+        config: Config,
+        rs485: Rs485Config,
+    ) -> Self {
+        let rxd = Self::rxd_pin(rxd);
+        let txd = Self::txd_pin(Some(txd));
+
+        into_ref!(usart);
+
+        let mut usart = Self::new_inner(usart, rxd, txd, None, None, rx_dma, tx_dma, config);
+        usart.tx.de = Some(DriverEnable::new(de, rs485));
+        usart
+    }
+
+    /// 新建单线半双工模式的串口
+    ///
+    /// 收发共用 `txd` 一根开漏的线，发送期间自动屏蔽接收器，自己发出的回显会被丢弃
+    pub fn new_half_duplex(
+        usart: impl Peripheral<P = T> + 'd,
+        txd: impl Peripheral<P = impl TxPin<T>> + 'd,
+
+        rx_dma: Option<DmaChannel<'d, DMA, M>>,
+        tx_dma: Option<DmaChannel<'d, DMA, M>>,
+
+        config: Config,
+    ) -> Self {
+        let txd = Self::txd_pin(Some(txd));
+
+        into_ref!(usart);
+
+        let mut usart = Self::new_inner(usart, None, txd, None, None, rx_dma, tx_dma, config);
+        T::set_half_duplex(true);
+        // 接收器内部连接到 tx 引脚
+        T::rx_enable(true);
+        usart.tx.de = Some(DriverEnable::half_duplex());
+        usart
+    }
+
+    /// 初始化 rxd 引脚
+    fn rxd_pin(
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+    ) -> Option<PeripheralRef<'d, AnyPin>> {
+        rxd.map(|rxd| {
+            into_ref!(rxd);
+            rxd.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::OpenDrain);
+            rxd.map_into()
+        })
+    }
+
+    /// 初始化 txd 引脚
+    fn txd_pin(
+        txd: Option<impl Peripheral<P = impl TxPin<T>> + 'd>,
+    ) -> Option<PeripheralRef<'d, AnyPin>> {
+        txd.map(|txd| {
+            into_ref!(txd);
+            txd.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::OpenDrain);
+            txd.map_into()
+        })
+    }
+
+    /// 初始化 rts 引脚，推挽输出
+    fn rts_pin(rts: impl Peripheral<P = impl RtsPin<T>> + 'd) -> PeripheralRef<'d, AnyPin> {
+        into_ref!(rts);
+        rts.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::PullUp);
+        rts.map_into()
+    }
+
+    /// 初始化 cts 引脚，上拉输入，悬空时不允许发送
+    fn cts_pin(cts: impl Peripheral<P = impl CtsPin<T>> + 'd) -> PeripheralRef<'d, AnyPin> {
+        into_ref!(cts);
+        cts.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::PullUp);
+        cts.map_into()
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn new_inner(
         _usart: PeripheralRef<'d, T>,
@@ -178,7 +352,9 @@ impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
         config: Config,
     ) -> Self {
         T::enable();
-        T::config(config);
+        // 构造时不检查波特率，需要检查时使用 `set_config`
+        T::config_nearest(&config);
+        T::set_flow_control(HwFlowCtrl::new(rts.is_some(), cts.is_some()));
 
         if M::is_async() {
             T::id().enable_interrupt();
@@ -191,8 +367,107 @@ impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
     }
 }
 
+impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
+    /// 重新配置串口，返回实际使用的 BRR 和波特率误差
+    ///
+    /// 波特率无法实现或误差超过 [`BAUD_RATE_TOLERANCE_PPM`] 时返回 [`Error::BaudRate`]，
+    /// 此时不修改任何配置。配置期间串口会短暂关闭，调用前需要确保数据已经发送完毕
+    pub fn set_config(&mut self, config: &Config) -> Result<BaudRate, Error> {
+        T::config(config, BAUD_RATE_TOLERANCE_PPM)
+    }
+
+    /// 只修改波特率，返回实际使用的 BRR 和波特率误差
+    pub fn set_baud_rate(&mut self, baud: u32) -> Result<BaudRate, Error> {
+        T::set_baud_rate(baud, BAUD_RATE_TOLERANCE_PPM)
+    }
+
+    /// 返回当前的波特率，由 BRR 和 pclk 计算得出
+    pub fn baud_rate(&self) -> u32 {
+        T::baud_rate()
+    }
+
+    /// 开启自动波特率检测，接收到下一个字符时硬件自动更新波特率
+    pub fn enable_auto_baud_rate(&mut self, mode: AutoBaudRateMode) {
+        T::auto_baud_rate_config(true, mode);
+        T::auto_baud_rate_request();
+    }
+
+    /// 关闭自动波特率检测，保持当前的波特率
+    pub fn disable_auto_baud_rate(&mut self) {
+        T::auto_baud_rate_config(false, AutoBaudRateMode::default());
+    }
+
+    /// 检查自动波特率检测的结果
+    fn auto_baud_rate_result() -> Option<Result<u32, Error>> {
+        if T::event_flag(Event::ABRE) {
+            T::event_clear(Event::ABRE);
+            return Some(Err(Error::AutoBaudRate));
+        }
+
+        if T::event_flag(Event::ABRF) {
+            return Some(Ok(T::baud_rate()));
+        }
+
+        None
+    }
+}
+
+impl<'d, T: Instance> AnyUsart<'d, T, Blocking> {
+    /// 先发送再接收，常用于单线半双工的请求和应答，返回接收到的数量
+    ///
+    /// 发送期间的回显会被丢弃，`rx` 接收满后返回
+    pub fn write_read_blocking(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<usize, Error> {
+        self.tx.write(tx)?;
+        self.rx.read_blocking(rx)
+    }
+
+    /// 开启自动波特率检测并阻塞等待结果，成功后返回测量到的波特率
+    ///
+    /// 检测使用的字符仍可以通过接收接口读取
+    pub fn detect_baud_rate_blocking(&mut self, mode: AutoBaudRateMode) -> Result<u32, Error> {
+        self.enable_auto_baud_rate(mode);
+
+        loop {
+            if let Some(rst) = Self::auto_baud_rate_result() {
+                return rst;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> AnyUsart<'d, T, Async> {
+    /// 先发送再接收，常用于单线半双工的请求和应答，返回接收到的数量
+    ///
+    /// 发送期间的回显会被丢弃，`rx` 接收满后返回
+    pub async fn write_read(&mut self, tx: &[u8], rx: &mut [u8]) -> Result<usize, Error> {
+        self.tx.write(tx).await?;
+        self.rx.read(rx).await
+    }
+
+    /// 开启自动波特率检测并异步等待结果，成功后返回测量到的波特率
+    ///
+    /// 自动波特率事件没有独立的中断，通过接收中断唤醒，检测使用的字符仍可以通过接收接口读取
+    pub async fn detect_baud_rate(&mut self, mode: AutoBaudRateMode) -> Result<u32, Error> {
+        self.enable_auto_baud_rate(mode);
+
+        let events = Event::RXNE | Event::FE;
+        let _disable = DropGuard::new(|| events.iter().for_each(|e| T::event_config(e, false)));
+
+        poll_fn(|cx| {
+            future::register_waker::<T>(cx.waker(), events);
+
+            match Self::auto_baud_rate_result() {
+                Some(rst) => Poll::Ready(rst),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
 impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
-    pub fn read_blocking(&self, buf: &mut [u8]) -> usize {
+    pub fn read_blocking(&self, buf: &mut [u8]) -> Result<usize, Error> {
         T::read_bytes_blocking(buf)
     }
 
@@ -201,7 +476,7 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
 
         if let Some(dma) = &mut self.rx_dma {
             // 删除上次的标志
-            let clear_events = Event::IDLE | Event::RXNE | Event::NE | Event::FE | Event::PE;
+            let clear_events = Event::IDLE | Event::ORE | Event::NE | Event::FE | Event::PE;
             clear_events.iter().for_each(|e| T::event_clear(e));
 
             // 返回dma 通道的映射值
@@ -209,7 +484,7 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
 
             dma.clear_flag(EnumSet::all());
             // 不管成功与否都关闭dma触发
-            let _tx_dmp_close = DropGuard::new(|| T::tx_dma_enable(false));
+            let _rx_dma_close = DropGuard::new(|| T::rx_dma_enable(false));
 
             // 配置dma channel
             dma.config(dma::Config::new_periph2mem(
@@ -232,15 +507,7 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
             T::rx_dma_enable(true);
 
             let remain = loop {
-                if T::event_flag(Event::FE) {
-                    return Err(Error::Frame);
-                }
-                if T::event_flag(Event::PE) {
-                    return Err(Error::Parity);
-                }
-                if T::event_flag(Event::NE) {
-                    return Err(Error::Noise);
-                }
+                T::check_error()?;
 
                 if dma.is_error() {
                     return Err(Error::DMA);
@@ -251,6 +518,7 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
                 }
 
                 if T::event_flag(Event::IDLE) {
+                    T::event_clear(Event::IDLE);
                     break dma.remain() as usize;
                 }
             };
@@ -260,13 +528,13 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
         }
     }
 
-    pub fn read_idle_blocking(&self, buf: &mut [u8]) -> usize {
+    pub fn read_idle_blocking(&self, buf: &mut [u8]) -> Result<usize, Error> {
         T::read_bytes_idle_blocking(buf)
     }
 
     pub fn nb_read(&self) -> Result<u8, nb::Error<Error>> {
-        if T::rx_ready() {
-            Ok(T::read_byte_blocking())
+        if T::rx_ready() || T::event_flag(Event::ORE) {
+            Ok(T::read_byte_blocking()?)
         } else {
             Err(nb::Error::WouldBlock)
         }
@@ -276,76 +544,150 @@ impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
 #[cfg(feature = "embassy")]
 impl<'d, T: Instance> UsartRx<'d, T, Async> {
     pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
-        let mut cnt = 0;
-        for v in buf {
-            let events = Event::RXNE | Event::PE | Event::NE | Event::FE | Event::ORE; //
-
-            let event_future = poll_fn(move |cx| {
-                events.iter().for_each(|e| {
-                    future::EVENT_WAKERS[T::id() as usize][e as usize].register(cx.waker());
-                    T::event_config(e, true);
-                });
-
-                // 查看事件
-                let mut events_happen = EnumSet::empty();
-                events.iter().for_each(|e| {
-                    if T::event_flag(e) {
-                        T::event_clear(e);
-                        events_happen |= e;
-                    }
-                });
-
-                // some event happen, so reset the event future
-                if !events_happen.is_empty() {
-                    events.iter().for_each(|e| {
-                        T::event_config(e, false);
-                    });
-                    return Poll::Ready(events_happen);
-                }
-
-                Poll::Pending
-            });
-
-            let events_happen = event_future.await;
-
-            // grab rx data or clear some flag
-            *v = T::read();
-            cnt += 1;
-
-            if events_happen != Event::RXNE {
-                return Err(Error::Others);
+        for v in buf.iter_mut() {
+            // 只有一直等待数据时才会返回 None
+            if let Some(data) = Self::read_byte(false).await? {
+                *v = data;
             }
         }
 
-        Ok(cnt)
+        Ok(buf.len())
     }
 
     pub async fn read_with_idle(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
         let mut cnt = 0;
-        let events = Event::RXNE | Event::PE | Event::NE | Event::FE | Event::ORE | Event::IDLE;
         for v in buf {
-            let event = EventFuture::<T>::new(events).await;
-            if event == Event::RXNE {
-                *v = T::read();
-                cnt += 1;
-            } else if event.contains(Event::IDLE) {
-                return Ok(cnt);
-            } else {
-                return Err(Error::Others);
+            match Self::read_byte(true).await? {
+                Some(data) => *v = data,
+                None => return Ok(cnt),
             }
+            cnt += 1;
         }
         Ok(cnt)
     }
+
+    /// 等待接收一个字节，`idle` 为真时串口空闲返回 None
+    async fn read_byte(idle: bool) -> Result<Option<u8>, Error> {
+        let events = if idle {
+            Event::RXNE | Event::IDLE
+        } else {
+            EnumSet::empty() | Event::RXNE
+        };
+
+        let ready = poll_fn(|cx| {
+            future::register_waker::<T>(cx.waker(), events);
+
+            // 溢出时 RXNE 也是置位的，错误标志和 RXNE 同时置位
+            if T::event_flag(Event::RXNE) || T::event_flag(Event::ORE) {
+                return Poll::Ready(true);
+            }
+            if idle && T::event_flag(Event::IDLE) {
+                T::event_clear(Event::IDLE);
+                return Poll::Ready(false);
+            }
+            Poll::Pending
+        })
+        .await;
+
+        if !ready {
+            return Ok(None);
+        }
+
+        T::check_error()?;
+        Ok(Some(T::read()))
+    }
+
+    /// 通过 dma 接收数据，直到缓冲区满或串口空闲，返回接收到的数量
+    pub async fn read_until_idle(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let len = buf.len();
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let dma = self.rx_dma.as_mut().ok_or(Error::DMA)?;
+
+        // 删除上次的标志
+        let clear_events = Event::IDLE | Event::ORE | Event::NE | Event::FE | Event::PE;
+        clear_events.iter().for_each(|e| T::event_clear(e));
+
+        // 返回dma 通道的映射值
+        let (rx_dma_map, _tx_dma_map) = T::id().dma_channel_map();
+
+        dma.clear_flag(EnumSet::all());
+
+        // 配置dma channel
+        dma.config(dma::Config::new_periph2mem(
+            T::block().dr.as_ptr() as u32, // 数据寄存器
+            false,
+            dma::Burst::Single,
+            buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            dma::Priorities::Medium,
+            dma::RepeatMode::OneTime(len as u16),
+        ));
+
+        // 将 rx 信号绑定到 通道
+        dma.bind(rx_dma_map);
+        // 使能 dma channel
+        dma.start();
+
+        let events = Event::IDLE | Event::PE | Event::FE | Event::NE | Event::ORE;
+
+        // 不管成功与否（包括 future 被取消）都关闭dma触发
+        let _rx_dma_close = DropGuard::new(|| {
+            T::rx_dma_enable(false);
+            events.iter().for_each(|e| T::event_config(e, false));
+        });
+
+        // 串口开启dma
+        T::rx_dma_enable(true);
+
+        let dma = &*dma;
+        poll_fn(|cx| {
+            dma.register_waker(cx.waker(), dma::Event::TCIF | dma::Event::TEIF);
+            future::register_waker::<T>(cx.waker(), events);
+
+            if let Err(error) = T::check_error() {
+                return Poll::Ready(Err(error));
+            }
+
+            if dma.is_error() {
+                return Poll::Ready(Err(Error::DMA));
+            }
+
+            if dma.is_finish() {
+                return Poll::Ready(Ok(len));
+            }
+
+            if T::event_flag(Event::IDLE) {
+                T::event_clear(Event::IDLE);
+                return Poll::Ready(Ok(len - dma.remain() as usize));
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
 }
 
 impl<'d, T: Instance, M: Mode> UsartRx<'d, T, M> {
+    /// 重新配置串口，同时影响发送部分，参考 [`AnyUsart::set_config`]
+    pub fn set_config(&mut self, config: &Config) -> Result<BaudRate, Error> {
+        T::config(config, BAUD_RATE_TOLERANCE_PPM)
+    }
+
+    /// 只修改波特率，同时影响发送部分，参考 [`AnyUsart::set_baud_rate`]
+    pub fn set_baud_rate(&mut self, baud: u32) -> Result<BaudRate, Error> {
+        T::set_baud_rate(baud, BAUD_RATE_TOLERANCE_PPM)
+    }
+
     pub(crate) fn new(
         rxd: Option<PeripheralRef<'d, AnyPin>>,
         rts: Option<PeripheralRef<'d, AnyPin>>,
         rx_dma: Option<DmaChannel<'d, DMA, M>>,
     ) -> Self {
         T::rx_enable(rxd.is_some());
-        T::rts_enable(rts.is_none());
 
         Self {
             _p: PhantomData,
@@ -357,53 +699,55 @@ impl<'d, T: Instance, M: Mode> UsartRx<'d, T, M> {
 }
 
 impl<'d, T: Instance> UsartTx<'d, T, Blocking> {
-    fn write_bytes_dma_blocking(&mut self, buf: &[u8]) -> Result<(), Error> {
-        if let Some(dma) = &mut self.tx_dma {
-            // 返回dma 通道的映射值
-            let (_rx_dma_map, tx_dma_map) = T::id().dma_channel_map();
-
-            // 不管成功与否都关闭dma触发
-            let _tx_dmp_close = DropGuard::new(|| T::tx_dma_enable(false));
-
-            dma.clear_flag(EnumSet::all());
-
-            // 配置dma channel
-            dma.config(dma::Config::new_mem2periph(
-                buf.as_ptr() as u32,
-                true,
-                dma::Burst::Single,
-                T::block().dr.as_ptr() as u32,
-                false,
-                dma::Burst::Single,
-                dma::Priorities::Medium,
-                dma::RepeatMode::OneTime(buf.len() as u16),
-            ));
+    fn write_bytes_dma_blocking(
+        dma: &mut DmaChannel<'d, DMA, Blocking>,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        // 返回dma 通道的映射值
+        let (_rx_dma_map, tx_dma_map) = T::id().dma_channel_map();
+
+        // 不管成功与否都关闭dma触发
+        let _tx_dmp_close = DropGuard::new(|| T::tx_dma_enable(false));
+
+        dma.clear_flag(EnumSet::all());
+
+        // 配置dma channel
+        dma.config(dma::Config::new_mem2periph(
+            buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            T::block().dr.as_ptr() as u32,
+            false,
+            dma::Burst::Single,
+            dma::Priorities::Medium,
+            dma::RepeatMode::OneTime(buf.len() as u16),
+        ));
+
+        // 将 tx 信号绑定到 通道
+        dma.bind(tx_dma_map);
+        // 使能 dma channel
+        dma.start();
+
+        // 串口开启dma
+        T::tx_dma_enable(true);
+        // 等待dma传输完成
+        dma.wait_complet().map_err(|_| Error::DMA)?;
+        Ok(())
+    }
 
-            // 将 tx 信号绑定到 通道
-            dma.bind(tx_dma_map);
-            // 使能 dma channel
-            dma.start();
+    pub fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
+        // RS-485 模式下发送前占用总线
+        let bus = self.de.as_mut().map(|de| de.begin_blocking::<T>());
 
-            // 串口开启dma
-            T::tx_dma_enable(true);
-            // 等待dma传输完成
-            dma.wait_complet().map_err(|_| Error::DMA)?;
-            Ok(())
-        } else {
-            Err(Error::DMA)
+        match &mut self.tx_dma {
+            None => T::write_bytes_blocking(buf)?,
+            Some(dma) => Self::write_bytes_dma_blocking(dma, buf)?,
         }
-    }
 
-    fn write_bytes_blocking(&mut self, buf: &[u8]) -> Result<(), Error> {
-        T::write_bytes_blocking(buf)
-    }
-
-    pub fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
-        if self.tx_dma.is_none() {
-            self.write_bytes_blocking(buf)
-        } else {
-            self.write_bytes_dma_blocking(buf)
+        if let Some(bus) = bus {
+            bus.finish_blocking();
         }
+        Ok(())
     }
 
     fn flush(&self) -> nb::Result<(), Error> {
@@ -415,62 +759,69 @@ impl<'d, T: Instance> UsartTx<'d, T, Blocking> {
 #[cfg(feature = "embassy")]
 impl<'d, T: Instance> UsartTx<'d, T, Async> {
     pub async fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
-        if self.tx_dma.is_some() {
-            self.write_bytes_dma(buf).await
-        } else {
-            self.write_bytes(buf).await
+        // RS-485 模式下发送前占用总线
+        let bus = match &mut self.de {
+            Some(de) => Some(de.begin::<T>().await),
+            None => None,
+        };
+
+        match &mut self.tx_dma {
+            Some(dma) => Self::write_bytes_dma(dma, buf).await?,
+            None => Self::write_bytes(buf).await?,
         }
+
+        if let Some(bus) = bus {
+            bus.finish().await;
+        }
+        Ok(())
     }
 
     // 异步发送数据
-    async fn write_bytes(&mut self, buf: &[u8]) -> Result<(), Error> {
+    async fn write_bytes(buf: &[u8]) -> Result<(), Error> {
         let events = Event::TXE | Event::CTS;
         for v in buf {
             T::write(*v);
-            let rst = EventFuture::<T>::new(events).await;
-            if rst != Event::TXE {
-                return Err(Error::Others);
-            }
+            // CTS 无效时硬件会暂停发送，CTS 的变化只需继续等待 TXE
+            while !EventFuture::<T>::new(events).await.contains(Event::TXE) {}
         }
         Ok(())
     }
 
     /// 通过dma异步发送数据
-    async fn write_bytes_dma(&mut self, buf: &[u8]) -> Result<(), Error> {
-        if let Some(dma) = &mut self.tx_dma {
-            // 返回dma 通道的映射值
-            let (_rx_dma_map, tx_dma_map) = T::id().dma_channel_map();
-
-            // 不管成功与否都关闭dma触发
-            let _tx_dmp_close = DropGuard::new(|| T::tx_dma_enable(false));
+    async fn write_bytes_dma(
+        dma: &mut DmaChannel<'d, DMA, Async>,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        // 返回dma 通道的映射值
+        let (_rx_dma_map, tx_dma_map) = T::id().dma_channel_map();
+
+        // 不管成功与否都关闭dma触发
+        let _tx_dmp_close = DropGuard::new(|| T::tx_dma_enable(false));
+
+        // 配置dma channel
+        dma.config(dma::Config::new_mem2periph(
+            buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            T::block().dr.as_ptr() as u32,
+            false,
+            dma::Burst::Single,
+            dma::Priorities::Medium,
+            dma::RepeatMode::OneTime(buf.len() as u16),
+        ));
+
+        // 将 tx 信号绑定到 通道
+        dma.bind(tx_dma_map);
+        // 使能 dma channel
+        dma.start();
+
+        // 串口开启dma
+        T::tx_dma_enable(true);
+
+        // 等待dma传输完成
+        dma.wait_complet().await.map_err(|_| Error::DMA)?;
 
-            // 配置dma channel
-            dma.config(dma::Config::new_mem2periph(
-                buf.as_ptr() as u32,
-                true,
-                dma::Burst::Single,
-                T::block().dr.as_ptr() as u32,
-                false,
-                dma::Burst::Single,
-                dma::Priorities::Medium,
-                dma::RepeatMode::OneTime(buf.len() as u16),
-            ));
-
-            // 将 tx 信号绑定到 通道
-            dma.bind(tx_dma_map);
-            // 使能 dma channel
-            dma.start();
-
-            // 串口开启dma
-            T::tx_dma_enable(true);
-
-            // 等待dma传输完成
-            dma.wait_complet().await.map_err(|_| Error::DMA)?;
-
-            Ok(())
-        } else {
-            Err(Error::DMA)
-        }
+        Ok(())
     }
 
     pub async fn flush(&mut self) -> Result<(), Error> {
@@ -480,19 +831,29 @@ impl<'d, T: Instance> UsartTx<'d, T, Async> {
 }
 
 impl<'d, T: Instance, M: Mode> UsartTx<'d, T, M> {
+    /// 重新配置串口，同时影响接收部分，参考 [`AnyUsart::set_config`]
+    pub fn set_config(&mut self, config: &Config) -> Result<BaudRate, Error> {
+        T::config(config, BAUD_RATE_TOLERANCE_PPM)
+    }
+
+    /// 只修改波特率，同时影响接收部分，参考 [`AnyUsart::set_baud_rate`]
+    pub fn set_baud_rate(&mut self, baud: u32) -> Result<BaudRate, Error> {
+        T::set_baud_rate(baud, BAUD_RATE_TOLERANCE_PPM)
+    }
+
     pub(crate) fn new(
         txd: Option<PeripheralRef<'d, AnyPin>>,
         cts: Option<PeripheralRef<'d, AnyPin>>,
         tx_dma: Option<DmaChannel<'d, DMA, M>>,
     ) -> Self {
         T::tx_enable(txd.is_some());
-        T::cts_enable(cts.is_some());
 
         Self {
             _p: PhantomData,
             _txd: txd,
             _cts: cts,
             tx_dma,
+            de: None,
         }
     }
 }
@@ -539,9 +900,96 @@ impl<'d, T: Instance> embedded_hal_027::serial::Write<u8> for UsartTx<'d, T, Blo
     }
 }
 
+impl<'d, T: Instance> embedded_hal_nb::serial::ErrorType for AnyUsart<'d, T, Blocking> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::ErrorType for UsartRx<'d, T, Blocking> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::ErrorType for UsartTx<'d, T, Blocking> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::Read<u8> for AnyUsart<'d, T, Blocking> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.rx.nb_read()
+    }
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::Read<u8> for UsartRx<'d, T, Blocking> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        self.nb_read()
+    }
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::Write<u8> for AnyUsart<'d, T, Blocking> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        embedded_hal_nb::serial::Write::write(&mut self.tx, word)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        embedded_hal_nb::serial::Write::flush(&mut self.tx)
+    }
+}
+
+impl<'d, T: Instance> embedded_hal_nb::serial::Write<u8> for UsartTx<'d, T, Blocking> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        // RS-485 和半双工模式需要控制总线，退化为阻塞发送
+        if self.de.is_some() {
+            return Ok(UsartTx::<T, Blocking>::write(self, &[word])?);
+        }
+
+        if !T::event_flag(Event::TXE) {
+            return Err(nb::Error::WouldBlock);
+        }
+        T::write(word);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if T::event_flag(Event::TC) {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+impl<'d, T: Instance> core::fmt::Write for UsartTx<'d, T, Blocking> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        UsartTx::<T, Blocking>::write(self, s.as_bytes()).map_err(|_| core::fmt::Error)
+    }
+}
+
 impl embedded_io::Error for Error {
     fn kind(&self) -> embedded_io::ErrorKind {
-        embedded_io::ErrorKind::Other
+        match *self {
+            Error::StartTimeout | Error::ReadTimeout | Error::WriteTimeout => {
+                embedded_io::ErrorKind::TimedOut
+            }
+            Error::Overrun
+            | Error::Break
+            | Error::Frame
+            | Error::Parity
+            | Error::Noise
+            | Error::AutoBaudRate => embedded_io::ErrorKind::InvalidData,
+            Error::BaudRate => embedded_io::ErrorKind::InvalidInput,
+            Error::DMA | Error::Others => embedded_io::ErrorKind::Other,
+        }
+    }
+}
+
+impl embedded_hal_nb::serial::Error for Error {
+    fn kind(&self) -> embedded_hal_nb::serial::ErrorKind {
+        match *self {
+            Error::Overrun => embedded_hal_nb::serial::ErrorKind::Overrun,
+            Error::Break | Error::Frame => embedded_hal_nb::serial::ErrorKind::FrameFormat,
+            Error::Parity => embedded_hal_nb::serial::ErrorKind::Parity,
+            Error::Noise => embedded_hal_nb::serial::ErrorKind::Noise,
+            _ => embedded_hal_nb::serial::ErrorKind::Other,
+        }
     }
 }
 
@@ -561,7 +1009,7 @@ impl<'d, T: Instance> embedded_io::Read for AnyUsart<'d, T, Blocking> {
 
 impl<'d, T: Instance> embedded_io::Read for UsartRx<'d, T, Blocking> {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        Ok(T::read_bytes_blocking(buf))
+        T::read_bytes_blocking(buf)
     }
 }
 
@@ -0,0 +1,220 @@
+//! 多处理器通讯
+//!
+//! 使用 9 位数据帧（不能开启校验），第 9 位为 1 表示地址帧。从机进入静默模式后，只有收到与本节点地址匹配的
+//! 地址帧（硬件只比较低 4 位）才会被唤醒，之后的数据帧一直接收到下一个地址帧为止。
+//! 从机的接收接口会再用软件比较完整的 8 位地址，因此总线上可以挂载更多的节点。
+//!
+//! 主机发送完一个报文后需要保持至少一个字符时间的空闲，从机以此判断报文结束。
+//!
+//! ```rust, ignore
+//! let config = Config { data_bits: DataBits::Nine, ..Default::default() };
+//! // 主机
+//! master.tx.send_address(0x23)?;
+//! master.tx.write_9bit(&[0x01, 0x02])?;
+//! // 从机
+//! slave.set_multiprocessor(0x23, WakeUpMethod::AddressMark);
+//! let n = slave.rx.read_addressed(0x23, &mut buf)?;
+//! ```
+
+#[cfg(feature = "embassy")]
+use super::future::{register_waker, EventFuture};
+use super::{AnyUsart, Error, Event, Instance, UsartRx, UsartTx, WakeUpMethod};
+#[cfg(feature = "embassy")]
+use crate::mode::Async;
+use crate::mode::{Blocking, Mode};
+#[cfg(feature = "embassy")]
+use core::future::poll_fn;
+#[cfg(feature = "embassy")]
+use core::task::Poll;
+#[cfg(feature = "embassy")]
+use drop_move::DropGuard;
+#[cfg(feature = "embassy")]
+use enumset::EnumSet;
+
+/// 9 位数据帧中的地址标记位
+const ADDRESS_MARK: u16 = 0x100;
+
+impl<'d, T: Instance, M: Mode> AnyUsart<'d, T, M> {
+    /// 配置本节点的地址和静默模式的唤醒方式
+    ///
+    /// 硬件只比较地址的低 4 位
+    pub fn set_multiprocessor(&mut self, address: u8, method: WakeUpMethod) {
+        T::set_node_address(address);
+        T::set_wake_up_method(method);
+    }
+
+    /// 进入静默模式，直到满足唤醒条件
+    pub fn mute(&mut self) {
+        T::set_mute(true);
+    }
+
+    /// 是否处于静默模式
+    pub fn is_muted(&self) -> bool {
+        T::is_mute()
+    }
+}
+
+/// 检查是否配置为 9 位数据且没有校验位，否则地址标记位会被截断或者被当作校验位
+fn check_nine_bit<T: Instance>() -> Result<(), Error> {
+    if T::is_nine_bit() {
+        Ok(())
+    } else {
+        Err(Error::Others)
+    }
+}
+
+impl<'d, T: Instance> UsartTx<'d, T, Blocking> {
+    /// 发送地址帧，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub fn send_address(&mut self, address: u8) -> Result<(), Error> {
+        self.write_9bit(&[ADDRESS_MARK | address as u16])
+    }
+
+    /// 发送 9 位数据，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub fn write_9bit(&mut self, buf: &[u16]) -> Result<(), Error> {
+        check_nine_bit::<T>()?;
+        for word in buf {
+            while !T::event_flag(Event::TXE) {}
+            T::write_word(*word);
+        }
+        Ok(())
+    }
+}
+
+impl<'d, T: Instance> UsartRx<'d, T, Blocking> {
+    /// 进入静默模式，接收发给 `address` 的一个报文，返回接收到的数量
+    ///
+    /// 报文在总线空闲、收到下一个地址帧或 `buf` 满时结束，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub fn read_addressed(&mut self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        check_nine_bit::<T>()?;
+
+        // 清除之前残留的空闲标志，避免报文被提前结束
+        if T::event_flag(Event::IDLE) {
+            T::event_clear(Event::IDLE);
+        }
+
+        // 等待发给本节点的地址帧
+        loop {
+            T::set_mute(true);
+            while !T::rx_ready() {}
+            T::check_error()?;
+
+            let word = T::read_word();
+            if word & ADDRESS_MARK != 0 && word as u8 == address {
+                break;
+            }
+        }
+
+        let mut cnt = 0;
+        while cnt < buf.len() {
+            // 等待数据帧，空闲或者被硬件重新静默表示报文结束
+            while !T::rx_ready() {
+                if T::is_mute() {
+                    return Ok(cnt);
+                }
+                if T::event_flag(Event::IDLE) {
+                    T::event_clear(Event::IDLE);
+                    return Ok(cnt);
+                }
+            }
+            T::check_error()?;
+
+            let word = T::read_word();
+            if word & ADDRESS_MARK != 0 {
+                // 下一个报文的地址帧
+                return Ok(cnt);
+            }
+            buf[cnt] = word as u8;
+            cnt += 1;
+        }
+        Ok(cnt)
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> UsartTx<'d, T, Async> {
+    /// 发送地址帧，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub async fn send_address(&mut self, address: u8) -> Result<(), Error> {
+        self.write_9bit(&[ADDRESS_MARK | address as u16]).await
+    }
+
+    /// 发送 9 位数据，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub async fn write_9bit(&mut self, buf: &[u16]) -> Result<(), Error> {
+        check_nine_bit::<T>()?;
+        for word in buf {
+            if !T::event_flag(Event::TXE) {
+                EventFuture::<T>::new(EnumSet::empty() | Event::TXE).await;
+            }
+            T::write_word(*word);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl<'d, T: Instance> UsartRx<'d, T, Async> {
+    /// 进入静默模式，接收发给 `address` 的一个报文，返回接收到的数量
+    ///
+    /// 报文在总线空闲、收到下一个地址帧或 `buf` 满时结束，没有配置为 9 位数据时返回 [`Error::Others`]
+    pub async fn read_addressed(&mut self, address: u8, buf: &mut [u8]) -> Result<usize, Error> {
+        check_nine_bit::<T>()?;
+
+        let events = Event::RXNE | Event::IDLE | Event::PE | Event::FE;
+        let _disable = DropGuard::new(|| events.iter().for_each(|e| T::event_config(e, false)));
+
+        // 清除之前残留的空闲标志，避免报文被提前结束
+        if T::event_flag(Event::IDLE) {
+            T::event_clear(Event::IDLE);
+        }
+
+        // 等待发给本节点的地址帧
+        loop {
+            T::set_mute(true);
+            if let Some(word) = Self::wait_word(events, false).await? {
+                if word & ADDRESS_MARK != 0 && word as u8 == address {
+                    break;
+                }
+            }
+        }
+
+        let mut cnt = 0;
+        while cnt < buf.len() {
+            match Self::wait_word(events, true).await? {
+                Some(word) if word & ADDRESS_MARK == 0 => {
+                    buf[cnt] = word as u8;
+                    cnt += 1;
+                }
+                // 空闲或下一个报文的地址帧
+                _ => break,
+            }
+        }
+        Ok(cnt)
+    }
+
+    /// 等待一个数据帧，`stop_on_idle` 时遇到空闲或静默返回 `None`
+    async fn wait_word(events: EnumSet<Event>, stop_on_idle: bool) -> Result<Option<u16>, Error> {
+        poll_fn(|cx| {
+            register_waker::<T>(cx.waker(), events);
+
+            if let Err(e) = T::check_error() {
+                return Poll::Ready(Err(e));
+            }
+
+            if T::rx_ready() {
+                return Poll::Ready(Ok(Some(T::read_word())));
+            }
+
+            if stop_on_idle {
+                if T::is_mute() {
+                    return Poll::Ready(Ok(None));
+                }
+                if T::event_flag(Event::IDLE) {
+                    T::event_clear(Event::IDLE);
+                    return Poll::Ready(Ok(None));
+                }
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+}
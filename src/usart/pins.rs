@@ -1,4 +1,4 @@
-use super::{CtsPin, RtsPin, RxPin, TxPin};
+use super::{CkPin, CtsPin, RtsPin, RxPin, TxPin};
 use crate::gpio::{self, gpioa, gpiob, gpiof};
 use crate::macro_def::impl_pin_af;
 use crate::mcu::peripherals;
@@ -18,6 +18,8 @@ impl_pin_af!(gpioa, PA2, USART2, TxPin, AF4);
 impl_pin_af!(gpioa, PA3, USART1, RxPin, AF1);
 impl_pin_af!(gpioa, PA3, USART2, RxPin, AF4);
 
+impl_pin_af!(gpioa, PA4, USART1, CkPin, AF1);
+impl_pin_af!(gpioa, PA4, USART2, CkPin, AF4);
 impl_pin_af!(gpioa, PA4, USART2, TxPin, AF9);
 
 impl_pin_af!(gpioa, PA5, USART2, TxPin, AF9);
@@ -25,6 +27,8 @@ impl_pin_af!(gpioa, PA5, USART2, TxPin, AF9);
 impl_pin_af!(gpioa, PA7, USART1, TxPin, AF8);
 impl_pin_af!(gpioa, PA7, USART2, TxPin, AF9);
 
+impl_pin_af!(gpioa, PA8, USART1, CkPin, AF1);
+impl_pin_af!(gpioa, PA8, USART2, CkPin, AF4);
 impl_pin_af!(gpioa, PA8, USART1, TxPin, AF8);
 impl_pin_af!(gpioa, PA8, USART2, TxPin, AF9);
 
@@ -0,0 +1,195 @@
+//! 带分隔符和超时的串口读取
+//!
+//! 在任意 `embedded_io_async::Read` 上增加一个 `N` 字节的暂存区，已经读出但还没有交给调用者的数据
+//! 都留在暂存区中，下一次调用继续使用。所有方法都只在等待底层读取时挂起，数据只在返回前一次性
+//! 交给调用者，future 被取消时不会丢失数据。
+//!
+//! 底层读取对象应当使用 [`BufferedUartRx`](super::BufferedUartRx) 或
+//! [`RingBufferedUartRx`](super::RingBufferedUartRx)，两次调用之间到达的数据才不会因为溢出而丢失。
+//!
+//! ```rust, ignore
+//! let mut reader = UartReader::<_, 64>::new(rx);
+//! let mut lines = reader.lines();
+//! loop {
+//!     let line = lines.next().await?;
+//!     if line == b"OK" {
+//!         break;
+//!     }
+//! }
+//! ```
+
+use embassy_time::{with_timeout, Duration};
+use embedded_io_async::Read;
+
+/// 读取错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadError<E> {
+    /// 底层读取错误
+    Read(E),
+    /// 超时，已经收到的数据保留在暂存区中
+    Timeout,
+    /// 一行的长度超过了暂存区，这些数据被丢弃
+    Overflow,
+}
+
+/// 带暂存区的串口读取对象
+pub struct UartReader<R: Read, const N: usize> {
+    inner: R,
+    buf: [u8; N],
+    start: usize,
+    end: usize,
+}
+
+impl<R: Read, const N: usize> UartReader<R, N> {
+    pub fn new(inner: R) -> Self {
+        assert!(N > 0);
+        Self {
+            inner,
+            buf: [0; N],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// 返回底层读取对象，暂存区中的数据被丢弃
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// 暂存区中还没有读取的数据
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+
+    /// 读取直到收到 `byte`（包含在返回的数据中），返回读取的数量
+    ///
+    /// `buf` 或者暂存区被填满时也会返回，此时最后一个字节不是 `byte`
+    pub async fn read_until(
+        &mut self,
+        byte: u8,
+        buf: &mut [u8],
+    ) -> Result<usize, ReadError<R::Error>> {
+        loop {
+            let data = self.buffered();
+            let limit = data.len().min(buf.len());
+            let cnt = match data[..limit].iter().position(|v| *v == byte) {
+                Some(i) => Some(i + 1),
+                None if limit == buf.len() || data.len() == N => Some(limit),
+                None => None,
+            };
+
+            if let Some(cnt) = cnt {
+                buf[..cnt].copy_from_slice(&data[..cnt]);
+                self.start += cnt;
+                return Ok(cnt);
+            }
+
+            self.fill().await?;
+        }
+    }
+
+    /// 在 `timeout` 内读满 `buf`，`buf` 的长度不能超过暂存区
+    ///
+    /// 超时返回 [`ReadError::Timeout`]，已经收到的数据留在暂存区中
+    pub async fn read_exact_with_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(), ReadError<R::Error>> {
+        assert!(buf.len() <= N);
+
+        let len = buf.len();
+        with_timeout(timeout, async {
+            while self.end - self.start < len {
+                self.fill().await?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|_| ReadError::Timeout)??;
+
+        buf.copy_from_slice(&self.buf[self.start..self.start + len]);
+        self.start += len;
+        Ok(())
+    }
+
+    /// 逐行读取
+    pub fn lines(&mut self) -> Lines<'_, R, N> {
+        Lines { reader: self }
+    }
+
+    /// 读取一行，不包含行尾的 CR/LF，空行被跳过
+    async fn next_line(&mut self) -> Result<&[u8], ReadError<R::Error>> {
+        let is_eol = |v: &u8| *v == b'\r' || *v == b'\n';
+        loop {
+            // 跳过上一行剩下的行尾，例如 CRLF 中的 LF
+            while self.start < self.end && is_eol(&self.buf[self.start]) {
+                self.start += 1;
+            }
+
+            if let Some(i) = self.buffered().iter().position(is_eol) {
+                let line = self.start..self.start + i;
+                self.start += i + 1;
+                return Ok(&self.buf[line]);
+            }
+
+            if self.end - self.start == N {
+                self.start = 0;
+                self.end = 0;
+                return Err(ReadError::Overflow);
+            }
+
+            self.fill().await?;
+        }
+    }
+
+    /// 从底层读取数据追加到暂存区，暂存区不能是满的
+    async fn fill(&mut self) -> Result<(), ReadError<R::Error>> {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        let cnt = self
+            .inner
+            .read(&mut self.buf[self.end..])
+            .await
+            .map_err(ReadError::Read)?;
+        self.end += cnt;
+        Ok(())
+    }
+}
+
+/// 逐行读取，由 [`UartReader::lines`] 创建
+pub struct Lines<'a, R: Read, const N: usize> {
+    reader: &'a mut UartReader<R, N>,
+}
+
+impl<'a, R: Read, const N: usize> Lines<'a, R, N> {
+    /// 等待完整的一行，不包含行尾的 CR/LF，空行被跳过
+    ///
+    /// 超过暂存区长度的一行返回 [`ReadError::Overflow`]
+    pub async fn next(&mut self) -> Result<&[u8], ReadError<R::Error>> {
+        self.reader.next_line().await
+    }
+}
+
+impl<R: Read, const N: usize> embedded_io_async::ErrorType for UartReader<R, N> {
+    type Error = R::Error;
+}
+
+impl<R: Read, const N: usize> Read for UartReader<R, N> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        // 先读取暂存区中的数据
+        if self.start == self.end {
+            return self.inner.read(buf).await;
+        }
+
+        let data = self.buffered();
+        let cnt = data.len().min(buf.len());
+        buf[..cnt].copy_from_slice(&data[..cnt]);
+        self.start += cnt;
+        Ok(cnt)
+    }
+}
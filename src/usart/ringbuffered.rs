@@ -0,0 +1,382 @@
+//! 基于 DMA 循环模式的串口接收
+//!
+//! DMA 通道以 `RepeatMode::Repeat` 方式不停地把数据搬运到用户提供的环形缓冲区，
+//! 通过 DMA 的 HTIF/TCIF 以及串口的 IDLE 事件唤醒读取任务，读取任务只需要在缓冲区
+//! 被写满一圈之前把数据取走即可，不再需要每个字节都唤醒一次。
+//!
+//! ```rust, ignore
+//! let usart = AnyUsart::new(p.USART1, Some(rx), Some(tx), Some(rx_dma), None, Default::default());
+//! let (rx, _tx) = usart.split();
+//! static mut BUF: [u8; 64] = [0; 64];
+//! let mut rx = rx.into_ring_buffered(unsafe { &mut BUF }).unwrap();
+//! let n = rx.read(&mut buf).await?;
+//! ```
+
+use super::future::register_waker;
+use super::{Error, Event, Instance, UsartRx};
+use crate::dma::{self, DmaChannel};
+use crate::mcu::peripherals::DMA;
+use crate::mode::Async;
+use core::future::poll_fn;
+use core::task::Poll;
+use enumset::EnumSet;
+
+/// 环形缓冲区的读写位置记录
+///
+/// DMA 的写位置只能通过剩余计数得到，而回绕只能通过 TCIF 标志得知。
+/// 两者无法同时原子地读取，所以每次观察都在读标志前后各采样一次写位置。
+struct RingState {
+    /// 缓冲区长度
+    len: usize,
+    /// 下一个要读取的位置
+    read: usize,
+    /// 上一次观察到的 DMA 写位置
+    write: usize,
+    /// 尚未读取的数据量
+    unread: usize,
+    /// 上一次观察时，读取 TCIF 之后才发生了回绕，下次的 TCIF 标志已经计算过了
+    skip_tc: bool,
+}
+
+impl RingState {
+    const fn new(len: usize) -> Self {
+        Self {
+            len,
+            read: 0,
+            write: 0,
+            unread: 0,
+            skip_tc: false,
+        }
+    }
+
+    /// 丢弃所有未读数据，从当前写位置重新开始
+    fn reset(&mut self, write: usize) {
+        self.read = write;
+        self.write = write;
+        self.unread = 0;
+        self.skip_tc = false;
+    }
+
+    /// 根据一次观察更新未读数量
+    ///
+    /// - `before`: 读取 TCIF 之前的写位置
+    /// - `tc`: TCIF 标志
+    /// - `after`: 读取 TCIF 之后的写位置
+    ///
+    /// 当 DMA 超过读位置一整圈时返回 `Err(())`
+    fn update(&mut self, before: usize, tc: bool, after: usize) -> Result<usize, ()> {
+        // 两次采样之间发生了回绕，但标志在回绕前被读取，回绕已经体现在写位置上
+        let late_wrap = !tc && after < before;
+        let wrapped = (tc && !self.skip_tc) || late_wrap;
+        self.skip_tc = late_wrap;
+
+        let mut advance = (after + self.len - self.write) % self.len;
+        if wrapped && after >= self.write {
+            // 回绕后又越过了上次的位置，整整转了一圈
+            advance += self.len;
+        }
+
+        self.write = after;
+        self.unread += advance;
+
+        if self.unread > self.len {
+            return Err(());
+        }
+        Ok(self.unread)
+    }
+
+    /// 消耗 `cnt` 个数据
+    fn consume(&mut self, cnt: usize) {
+        self.read = (self.read + cnt) % self.len;
+        self.unread -= cnt;
+    }
+}
+
+/// 使用 DMA 循环模式的串口接收对象
+pub struct RingBufferedUartRx<'d, T: Instance> {
+    _rx: UsartRx<'d, T, Async>,
+    dma: DmaChannel<'d, DMA, Async>,
+    buf: &'d mut [u8],
+    state: RingState,
+}
+
+impl<'d, T: Instance> UsartRx<'d, T, Async> {
+    /// 转换为 DMA 循环接收对象，需要在创建串口时提供 rx dma 通道
+    ///
+    /// 缓冲区长度需要在 2 ~ 65535 之间，否则返回 [`Error::Others`]
+    pub fn into_ring_buffered(
+        mut self,
+        buf: &'d mut [u8],
+    ) -> Result<RingBufferedUartRx<'d, T>, Error> {
+        if buf.len() < 2 || buf.len() > u16::MAX as usize {
+            return Err(Error::Others);
+        }
+
+        let dma = self.rx_dma.take().ok_or(Error::DMA)?;
+        let mut rx = RingBufferedUartRx {
+            _rx: self,
+            dma,
+            state: RingState::new(buf.len()),
+            buf,
+        };
+        rx.start();
+
+        Ok(rx)
+    }
+}
+
+impl<'d, T: Instance> RingBufferedUartRx<'d, T> {
+    /// 开始接收，之前缓冲区中的数据将被丢弃
+    pub fn start(&mut self) {
+        self.stop();
+
+        // 删除上次的标志
+        let clear_events = Event::IDLE | Event::NE | Event::FE | Event::PE | Event::ORE;
+        clear_events.iter().for_each(|e| T::event_clear(e));
+
+        // 返回dma 通道的映射值
+        let (rx_dma_map, _tx_dma_map) = T::id().dma_channel_map();
+
+        self.dma.clear_flag(EnumSet::all());
+        self.dma.config(dma::Config::new_periph2mem(
+            T::block().dr.as_ptr() as u32, // 数据寄存器
+            false,
+            dma::Burst::Single,
+            self.buf.as_ptr() as u32,
+            true,
+            dma::Burst::Single,
+            dma::Priorities::High,
+            dma::RepeatMode::Repeat(self.buf.len() as u16),
+        ));
+
+        // 将 rx 信号绑定到 通道
+        self.dma.bind(rx_dma_map);
+        self.state.reset(0);
+        // 使能 dma channel
+        self.dma.start();
+
+        // 串口开启dma
+        T::rx_dma_enable(true);
+    }
+
+    /// 停止接收
+    pub fn stop(&mut self) {
+        T::rx_dma_enable(false);
+        self.dma.stop();
+        self.dma
+            .disable_event(dma::Event::HTIF | dma::Event::TCIF | dma::Event::TEIF);
+        (Event::IDLE | Event::PE | Event::FE)
+            .iter()
+            .for_each(|e| T::event_config(e, false));
+    }
+
+    /// 返回环形缓冲区的长度
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// 读取数据，没有数据时等待直到有数据、串口空闲或发生错误
+    ///
+    /// 发生溢出时返回 [`Error::Overrun`]，缓冲区中未读取的数据会被丢弃，再次调用可以继续读取
+    pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        poll_fn(|cx| {
+            // 先注册唤醒器，避免检查之后到达的事件丢失
+            self.dma.register_waker(
+                cx.waker(),
+                dma::Event::HTIF | dma::Event::TCIF | dma::Event::TEIF,
+            );
+            register_waker::<T>(cx.waker(), Event::IDLE | Event::PE | Event::FE);
+
+            match self.read_available(buf) {
+                Ok(0) => Poll::Pending,
+                rst => Poll::Ready(rst),
+            }
+        })
+        .await
+    }
+
+    /// 不等待，读取当前缓冲区中已有的数据
+    pub fn read_available(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.check_error()?;
+
+        let unread = self.update()?;
+        let cnt = unread.min(buf.len());
+
+        let len = self.buf.len();
+        let ptr = self.buf.as_ptr();
+        for (i, v) in buf[..cnt].iter_mut().enumerate() {
+            // 缓冲区由 dma 写入，必须使用 volatile 读取
+            *v = unsafe { core::ptr::read_volatile(ptr.add((self.state.read + i) % len)) };
+        }
+        self.state.consume(cnt);
+
+        Ok(cnt)
+    }
+
+    /// 检查串口和 dma 的错误标志
+    fn check_error(&mut self) -> Result<(), Error> {
+        if self.dma.is_error() {
+            self.start();
+            return Err(Error::DMA);
+        }
+
+        T::check_error()?;
+
+        // 空闲事件仅用于唤醒
+        if T::event_flag(Event::IDLE) {
+            T::event_clear(Event::IDLE);
+        }
+
+        Ok(())
+    }
+
+    /// 更新 dma 写位置，返回未读数量
+    fn update(&mut self) -> Result<usize, Error> {
+        let before = self.position();
+        let tc = self.dma.is_finish();
+        if tc {
+            self.dma.clear_flag(EnumSet::empty() | dma::Event::TCIF);
+        }
+        let after = self.position();
+        self.dma.clear_flag(EnumSet::empty() | dma::Event::HTIF);
+
+        self.state.update(before, tc, after).map_err(|_| {
+            // 数据已被覆盖，从当前位置重新开始
+            self.state.reset(after);
+            Error::Overrun
+        })
+    }
+
+    /// dma 当前的写位置
+    #[inline]
+    fn position(&self) -> usize {
+        (self.buf.len() - self.dma.remain() as usize) % self.buf.len()
+    }
+}
+
+impl<'d, T: Instance> Drop for RingBufferedUartRx<'d, T> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl<'d, T: Instance> embedded_io_async::ErrorType for RingBufferedUartRx<'d, T> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_io_async::Read for RingBufferedUartRx<'d, T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        RingBufferedUartRx::read(self, buf).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 已经读取到 `pos` 的 16 字节环形缓冲区
+    fn state_at(pos: usize) -> RingState {
+        let mut state = RingState::new(16);
+        state.reset(pos);
+        state
+    }
+
+    #[test]
+    fn no_wrap() {
+        let mut state = RingState::new(16);
+        assert_eq!(state.update(0, false, 0), Ok(0));
+        assert_eq!(state.update(0, false, 5), Ok(5));
+
+        state.consume(3);
+        assert_eq!(state.read, 3);
+        assert_eq!(state.update(5, false, 8), Ok(5));
+
+        state.consume(5);
+        assert_eq!(state.read, 8);
+        assert_eq!(state.update(8, false, 8), Ok(0));
+    }
+
+    #[test]
+    fn single_wrap() {
+        // 10 -> 16 -> 4
+        let mut state = state_at(10);
+        assert_eq!(state.update(2, true, 4), Ok(10));
+
+        state.consume(10);
+        assert_eq!(state.read, 4);
+        // 标志已经清除，不会重复计算
+        assert_eq!(state.update(4, false, 6), Ok(2));
+    }
+
+    #[test]
+    fn wrap_to_end() {
+        // 写位置回到 0 时只能通过 TCIF 得知
+        let mut state = state_at(10);
+        assert_eq!(state.update(0, true, 0), Ok(6));
+
+        state.consume(6);
+        assert_eq!(state.read, 0);
+        assert_eq!(state.update(0, false, 0), Ok(0));
+    }
+
+    #[test]
+    fn full_lap() {
+        // 正好写满一圈，没有覆盖未读数据
+        let mut state = state_at(5);
+        assert_eq!(state.update(5, true, 5), Ok(16));
+
+        state.consume(16);
+        assert_eq!(state.read, 5);
+        assert_eq!(state.update(5, false, 5), Ok(0));
+    }
+
+    #[test]
+    fn late_tc() {
+        // 读取 TCIF 时还没有回绕，之后的采样已经回绕
+        let mut state = state_at(10);
+        assert_eq!(state.update(15, false, 1), Ok(7));
+        assert!(state.skip_tc);
+
+        // 下一次看到的 TCIF 已经计算过了
+        state.consume(7);
+        assert_eq!(state.update(1, true, 3), Ok(2));
+        assert!(!state.skip_tc);
+
+        // 之后的 TCIF 正常计算
+        state.consume(2);
+        assert_eq!(state.update(3, false, 14), Ok(11));
+        state.consume(11);
+        assert_eq!(state.update(14, true, 1), Ok(3));
+    }
+
+    #[test]
+    fn late_tc_without_wrap() {
+        // 回绕发生在第一次采样之前，标志和两次采样都已经体现了回绕
+        let mut state = state_at(10);
+        assert_eq!(state.update(1, true, 1), Ok(7));
+        assert!(!state.skip_tc);
+    }
+
+    #[test]
+    fn overrun() {
+        // 写位置回绕后越过了读位置
+        let mut state = state_at(4);
+        assert_eq!(state.update(6, true, 6), Err(()));
+
+        // 未读数据累积超过一圈
+        let mut state = state_at(0);
+        assert_eq!(state.update(0, false, 12), Ok(12));
+        assert_eq!(state.update(12, false, 15), Ok(15));
+        assert_eq!(state.update(15, true, 2), Err(()));
+
+        // 溢出后从当前位置重新开始
+        state.reset(2);
+        assert_eq!(state.update(2, false, 4), Ok(2));
+        state.consume(2);
+        assert_eq!(state.read, 4);
+    }
+}
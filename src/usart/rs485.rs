@@ -0,0 +1,140 @@
+//! RS-485 半双工收发器的驱动使能（DE/RE）控制
+//!
+//! 发送前使能收发器的驱动器，发送完成（TC 事件）后再释放总线，
+//! 避免过早释放导致最后一个字节被截断。
+//!
+//! 单线半双工模式没有驱动使能引脚，同样借助这里的接收屏蔽丢弃自己发出的回显。
+
+#[cfg(feature = "embassy")]
+use super::future::EventFuture;
+use super::{Event, Instance, Rs485Config};
+use crate::delay::delay_us;
+use crate::gpio::Output;
+use core::marker::PhantomData;
+use embedded_hal::digital::OutputPin;
+#[cfg(feature = "embassy")]
+use enumset::EnumSet;
+
+/// 收发器驱动使能引脚
+pub(super) struct DriverEnable<'d> {
+    /// 单线半双工模式下没有驱动使能引脚
+    pin: Option<Output<'d>>,
+    config: Rs485Config,
+    /// 发送前接收器是否处于开启状态
+    rx_enabled: bool,
+}
+
+impl<'d> DriverEnable<'d> {
+    pub(super) fn new(pin: Output<'d>, config: Rs485Config) -> Self {
+        let mut de = Self {
+            pin: Some(pin),
+            config,
+            rx_enabled: false,
+        };
+        // 默认处于接收状态
+        de.set_driver(false);
+        de
+    }
+
+    /// 单线半双工模式，只在发送期间屏蔽接收器
+    pub(super) fn half_duplex() -> Self {
+        Self {
+            pin: None,
+            config: Rs485Config {
+                mask_receiver: true,
+                ..Default::default()
+            },
+            rx_enabled: false,
+        }
+    }
+
+    #[inline]
+    fn set_driver(&mut self, en: bool) {
+        let level = en == self.config.de_active_high;
+        if let Some(pin) = &mut self.pin {
+            // 输出引脚的操作不会失败
+            let _ = if level { pin.set_high() } else { pin.set_low() };
+        }
+    }
+
+    /// 占用总线，需要时屏蔽接收器
+    fn acquire<T: Instance>(&mut self) {
+        if self.config.mask_receiver {
+            self.rx_enabled = T::is_rx_enable();
+            T::rx_enable(false);
+        }
+        // 清除之前的发送完成标志，确保等待的是本次发送
+        T::event_clear(Event::TC);
+        self.set_driver(true);
+    }
+
+    /// 释放总线，恢复接收器
+    fn release<T: Instance>(&mut self) {
+        self.set_driver(false);
+        if self.config.mask_receiver && self.rx_enabled {
+            T::rx_enable(true);
+        }
+    }
+
+    /// 发送前调用，阻塞等待驱动器建立时间
+    pub(super) fn begin_blocking<T: Instance>(&mut self) -> BusGuard<'_, 'd, T> {
+        self.acquire::<T>();
+        delay_us(self.config.assert_time_us as usize);
+        BusGuard {
+            de: self,
+            _t: PhantomData,
+        }
+    }
+
+    /// 发送前调用，异步等待驱动器建立时间
+    #[cfg(feature = "embassy")]
+    pub(super) async fn begin<T: Instance>(&mut self) -> BusGuard<'_, 'd, T> {
+        self.acquire::<T>();
+        guard_delay(self.config.assert_time_us).await;
+        BusGuard {
+            de: self,
+            _t: PhantomData,
+        }
+    }
+}
+
+/// 发送期间占用总线
+///
+/// 被丢弃时（发送出错或 future 被取消）立即释放总线
+pub(super) struct BusGuard<'a, 'd, T: Instance> {
+    de: &'a mut DriverEnable<'d>,
+    _t: PhantomData<T>,
+}
+
+impl<'a, 'd, T: Instance> BusGuard<'a, 'd, T> {
+    /// 等待最后一个字节发送完毕，经过释放保护时间后释放总线
+    pub(super) fn finish_blocking(self) {
+        T::tx_flush();
+        delay_us(self.de.config.deassert_time_us as usize);
+    }
+
+    /// 异步等待最后一个字节发送完毕，经过释放保护时间后释放总线
+    #[cfg(feature = "embassy")]
+    pub(super) async fn finish(self) {
+        EventFuture::<T>::new(EnumSet::empty() | Event::TC).await;
+        guard_delay(self.de.config.deassert_time_us).await;
+    }
+}
+
+/// 等待保护时间
+///
+/// 定时器的精度为一个节拍，不足一个节拍的保护时间使用阻塞延时，避免驱动器多占用总线将近一个节拍
+#[cfg(feature = "embassy")]
+async fn guard_delay(us: u32) {
+    if (us as u64) < 1_000_000 / embassy_time::TICK_HZ {
+        delay_us(us as usize);
+    } else {
+        embassy_time::Timer::after_micros(us as u64).await;
+    }
+}
+
+impl<'a, 'd, T: Instance> Drop for BusGuard<'a, 'd, T> {
+    fn drop(&mut self) {
+        self.de.release::<T>();
+    }
+}
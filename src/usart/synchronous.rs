@@ -0,0 +1,139 @@
+//! 同步模式
+//!
+//! 串口作为同步主机，在 CK 引脚上输出时钟，TX 作为数据输出，RX 作为数据输入，
+//! 可以当作一个 SPI 主机使用。硬件只支持低位先发送，[`BitOrder::Msb`] 由软件翻转每个字节的位序。
+//!
+//! ```rust, ignore
+//! let mut spi = UsartSync::new(p.USART2, Some(gpioa.PA3), gpioa.PA2, gpioa.PA4, Default::default(), Default::default());
+//! spi.transfer(&mut read, &write)?;
+//! ```
+
+use super::{AnyUsart, BitOrder, CkPin, Config, Error, Event, Instance, RxPin, SyncConfig, TxPin};
+use crate::gpio::{self, AnyPin};
+use crate::mode::Blocking;
+use embassy_hal_internal::{into_ref, Peripheral, PeripheralRef};
+
+/// 同步模式的串口，实现了 `embedded_hal::spi::SpiBus<u8>`
+pub struct UsartSync<'d, T: Instance> {
+    usart: AnyUsart<'d, T, Blocking>,
+    _ck: PeripheralRef<'d, AnyPin>,
+    bit_order: BitOrder,
+}
+
+impl<'d, T: Instance> UsartSync<'d, T> {
+    /// 新建同步模式的串口，不需要接收时 `rxd` 可以为 `None`
+    ///
+    /// `config` 中的波特率即为 CK 的时钟频率，停止位决定两个字节之间的间隔
+    pub fn new(
+        usart: impl Peripheral<P = T> + 'd,
+        rxd: Option<impl Peripheral<P = impl RxPin<T>> + 'd>,
+        txd: impl Peripheral<P = impl TxPin<T>> + 'd,
+        ck: impl Peripheral<P = impl CkPin<T>> + 'd,
+        config: Config,
+        sync_config: SyncConfig,
+    ) -> Self {
+        into_ref!(ck);
+        ck.set_instance_af(gpio::Speed::VeryHigh, gpio::PinIoType::PullUp);
+
+        let usart = AnyUsart::new(usart, rxd, Some(txd), None, None, config);
+        T::set_synchronous(true, sync_config);
+
+        Self {
+            usart,
+            _ck: ck.map_into(),
+            bit_order: sync_config.bit_order,
+        }
+    }
+
+    /// 返回内部的串口对象
+    pub fn inner(&mut self) -> &mut AnyUsart<'d, T, Blocking> {
+        &mut self.usart
+    }
+
+    /// 发送一个字节，同时接收一个字节
+    fn transfer_byte(&self, word: u8, read: bool) -> Result<u8, Error> {
+        while !T::event_flag(Event::TXE) {}
+        T::write(self.bit_order.convert(word));
+
+        if !read {
+            return Ok(0);
+        }
+
+        while !T::rx_ready() {}
+        T::check_error()?;
+        Ok(self.bit_order.convert(T::read()))
+    }
+
+    /// 接收是否使能
+    fn can_read() -> Result<bool, Error> {
+        if T::is_rx_enable() {
+            Ok(true)
+        } else {
+            Err(Error::Others)
+        }
+    }
+}
+
+impl<'d, T: Instance> Drop for UsartSync<'d, T> {
+    fn drop(&mut self) {
+        T::set_synchronous(false, SyncConfig::default());
+    }
+}
+
+impl embedded_hal::spi::Error for Error {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        match *self {
+            Error::Overrun => embedded_hal::spi::ErrorKind::Overrun,
+            Error::Break | Error::Frame => embedded_hal::spi::ErrorKind::FrameFormat,
+            _ => embedded_hal::spi::ErrorKind::Other,
+        }
+    }
+}
+
+impl<'d, T: Instance> embedded_hal::spi::ErrorType for UsartSync<'d, T> {
+    type Error = Error;
+}
+
+impl<'d, T: Instance> embedded_hal::spi::SpiBus<u8> for UsartSync<'d, T> {
+    fn read(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        Self::can_read()?;
+        for word in words {
+            *word = self.transfer_byte(0, true)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let read = T::is_rx_enable();
+        for word in words {
+            // 丢弃接收到的数据，避免溢出
+            self.transfer_byte(*word, read)?;
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
+        Self::can_read()?;
+        let len = read.len().max(write.len());
+        for i in 0..len {
+            let word = self.transfer_byte(write.get(i).copied().unwrap_or(0), true)?;
+            if let Some(v) = read.get_mut(i) {
+                *v = word;
+            }
+        }
+        Ok(())
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [u8]) -> Result<(), Self::Error> {
+        Self::can_read()?;
+        for word in words {
+            *word = self.transfer_byte(*word, true)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        T::tx_flush();
+        Ok(())
+    }
+}
@@ -1,15 +1,26 @@
 use embedded_time::rate::{Baud, Extensions};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
     StartTimeout,
     ReadTimeout,
     WriteTimeout,
+    /// DMA 传输错误
     DMA,
-    /// 噪音/校验/帧错误
+    /// 噪声错误
     Noise,
+    /// 帧错误，停止位为低电平
     Frame,
+    /// 校验错误
     Parity,
+    /// 接收溢出，数据未及时读取而丢失
+    Overrun,
+    /// 收到间隔字符（整个字符期间都是低电平）
+    Break,
+    /// 自动波特率检测失败
+    AutoBaudRate,
+    /// 当前时钟下无法实现该波特率，或者误差超过允许范围
+    BaudRate,
     Others,
 }
 
@@ -66,15 +77,131 @@ pub enum Parity {
     Odd = 2,
 }
 
-// /// 串口流控
-// #[derive(Default)]
-// pub enum HwFlowCtrl {
-//     #[default]
-//     None,
-//     Rts = 1,
-//     Cts = 2,
-//     RtsCts = 3,
-// }
+/// 串口流控
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum HwFlowCtrl {
+    /// 不使用硬件流控
+    #[default]
+    None = 0,
+    /// 仅 RTS
+    Rts = 1,
+    /// 仅 CTS
+    Cts = 2,
+    /// RTS 和 CTS
+    RtsCts = 3,
+}
+
+impl HwFlowCtrl {
+    pub(crate) fn new(rts: bool, cts: bool) -> Self {
+        match (rts, cts) {
+            (false, false) => Self::None,
+            (true, false) => Self::Rts,
+            (false, true) => Self::Cts,
+            (true, true) => Self::RtsCts,
+        }
+    }
+
+    /// 是否使能 RTS
+    pub fn rts(&self) -> bool {
+        matches!(self, Self::Rts | Self::RtsCts)
+    }
+
+    /// 是否使能 CTS
+    pub fn cts(&self) -> bool {
+        matches!(self, Self::Cts | Self::RtsCts)
+    }
+}
+
+/// RS-485 收发器配置
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rs485Config {
+    /// DE 引脚高电平有效
+    pub de_active_high: bool,
+    /// 使能驱动器到发送起始位之间的保护时间（微秒）
+    pub assert_time_us: u32,
+    /// 发送完成（TC）到释放驱动器之间的保护时间（微秒）
+    pub deassert_time_us: u32,
+    /// 发送期间关闭接收器，丢弃自己发出的回显
+    pub mask_receiver: bool,
+}
+
+impl Default for Rs485Config {
+    /// DE 高电平有效，无保护时间，发送期间屏蔽接收
+    fn default() -> Self {
+        Self {
+            de_active_high: true,
+            assert_time_us: 0,
+            deassert_time_us: 0,
+            mask_receiver: true,
+        }
+    }
+}
+
+/// 同步模式配置
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncConfig {
+    /// 时钟极性和相位
+    pub mode: embedded_hal::spi::Mode,
+    /// 最后一个数据位是否输出时钟脉冲
+    pub last_bit_clock: bool,
+    /// 数据位的顺序
+    pub bit_order: BitOrder,
+}
+
+impl Default for SyncConfig {
+    /// SPI 模式 0，最后一位输出时钟，低位先发送
+    fn default() -> Self {
+        Self {
+            mode: embedded_hal::spi::MODE_0,
+            last_bit_clock: true,
+            bit_order: BitOrder::Lsb,
+        }
+    }
+}
+
+/// 同步模式数据位的顺序
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum BitOrder {
+    /// 低位先发送，串口硬件的顺序
+    #[default]
+    Lsb,
+    /// 高位先发送，大多数 SPI 器件使用的顺序，由软件翻转每个字节的位序实现
+    Msb,
+}
+
+impl BitOrder {
+    /// 在软件位序和硬件位序之间转换
+    pub(crate) fn convert(&self, v: u8) -> u8 {
+        match self {
+            Self::Lsb => v,
+            Self::Msb => v.reverse_bits(),
+        }
+    }
+}
+
+/// 静默模式的唤醒方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum WakeUpMethod {
+    /// 总线空闲时唤醒
+    #[default]
+    IdleLine = 0,
+    /// 收到与本节点地址匹配的地址标记帧时唤醒
+    AddressMark = 1,
+}
+
+/// 自动波特率检测模式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum AutoBaudRateMode {
+    /// 测量起始位的宽度，首个字符需以 1 开头
+    #[default]
+    StartBit = 0,
+    /// 测量两个下降沿的间隔，首个字符需以 10 开头
+    FallingEdge = 1,
+    /// 使用 0x7F 字符检测
+    Frame0x7F = 2,
+    /// 使用 0x55 字符检测
+    Frame0x55 = 3,
+}
 
 /// Clock oversampling settings.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
@@ -102,6 +229,66 @@ impl From<OverSampling> for bool {
     }
 }
 
+/// 波特率的默认允许误差，单位百万分之一
+pub const BAUD_RATE_TOLERANCE_PPM: u32 = 20_000;
+
+/// 波特率寄存器的计算结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaudRate {
+    /// BRR 寄存器的值
+    pub brr: u16,
+    /// 实际的波特率
+    pub actual: u32,
+    /// 相对误差，单位百万分之一，实际波特率偏大时为正
+    pub error_ppm: i32,
+}
+
+impl BaudRate {
+    /// 根据外设时钟 `pclk` 计算 BRR 的值，不访问任何寄存器
+    ///
+    /// 波特率为 0 或者分频系数超出 BRR 的范围时返回 [`Error::BaudRate`]
+    pub fn new(pclk: u32, baud: u32, over_sampling: OverSampling) -> Result<Self, Error> {
+        if baud == 0 {
+            return Err(Error::BaudRate);
+        }
+
+        // 以 1/16（或 1/8）为单位的分频系数，四舍五入
+        let div = (pclk as u64 + baud as u64 / 2) / baud as u64;
+        let over = over_sampling.div() as u64;
+        let mantissa = div / over;
+        let fraction = div % over;
+        if mantissa == 0 || mantissa > 0xfff {
+            return Err(Error::BaudRate);
+        }
+
+        let actual = (pclk as u64 / div) as u32;
+        let error_ppm = ((actual as i64 - baud as i64) * 1_000_000 / baud as i64) as i32;
+
+        Ok(Self {
+            // 8 倍过采样时小数部分只有低 3 位有效
+            brr: ((mantissa << 4) | fraction) as u16,
+            actual,
+            error_ppm,
+        })
+    }
+
+    /// 与 [`Self::new`] 相同，但分频系数超出 BRR 的范围时取最接近的值，波特率为 0 时取最低的波特率
+    pub(crate) fn nearest_brr(pclk: u32, baud: u32, over_sampling: OverSampling) -> u16 {
+        let over = over_sampling.div() as u64;
+        let div = match baud {
+            0 => u64::MAX,
+            baud => (pclk as u64 + baud as u64 / 2) / baud as u64,
+        };
+        let div = div.clamp(over, 0xfff * over + over - 1);
+        (((div / over) << 4) | (div % over)) as u16
+    }
+
+    /// 误差是否在 `tolerance_ppm` 以内
+    pub fn within(&self, tolerance_ppm: u32) -> bool {
+        self.error_ppm.unsigned_abs() <= tolerance_ppm
+    }
+}
+
 /// Number of data bits.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub enum DataBits {
@@ -118,3 +305,115 @@ impl From<DataBits> for bool {
         value == DataBits::Nine
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baud_rate_24mhz() {
+        let rate = BaudRate::new(24_000_000, 115_200, OverSampling::Sixteen).unwrap();
+        assert_eq!(rate.brr, 0x0d0);
+        assert_eq!(rate.actual, 115_384);
+        assert_eq!(rate.error_ppm, 1597);
+
+        // 分频系数相同，8 倍过采样时整数部分翻倍
+        let rate = BaudRate::new(24_000_000, 115_200, OverSampling::Eight).unwrap();
+        assert_eq!(rate.brr, 0x1a0);
+        assert_eq!(rate.actual, 115_384);
+    }
+
+    #[test]
+    fn baud_rate_8mhz() {
+        let rate = BaudRate::new(8_000_000, 115_200, OverSampling::Sixteen).unwrap();
+        assert_eq!(rate.brr, 0x045);
+        assert_eq!(rate.actual, 115_942);
+        assert_eq!(rate.error_ppm, 6440);
+
+        let rate = BaudRate::new(8_000_000, 115_200, OverSampling::Eight).unwrap();
+        assert_eq!(rate.brr, 0x085);
+        assert_eq!(rate.actual, 115_942);
+    }
+
+    #[test]
+    fn baud_rate_eight_fraction() {
+        // 8 倍过采样时 BRR[3] 必须为 0
+        for baud in [300, 9600, 57_600, 115_200, 230_400, 460_800, 1_000_000] {
+            let rate = BaudRate::new(8_000_000, baud, OverSampling::Eight).unwrap();
+            assert!(rate.brr & 0x8 == 0, "baud {}", baud);
+        }
+
+        // 139 = 17 * 8 + 3
+        let rate = BaudRate::new(8_000_000, 57_600, OverSampling::Eight).unwrap();
+        assert_eq!(rate.brr, 0x113);
+    }
+
+    #[test]
+    fn baud_rate_out_of_range() {
+        assert_eq!(
+            BaudRate::new(8_000_000, 0, OverSampling::Sixteen),
+            Err(Error::BaudRate)
+        );
+
+        // 整数部分为 0
+        assert_eq!(
+            BaudRate::new(8_000_000, 1_000_000, OverSampling::Sixteen),
+            Err(Error::BaudRate)
+        );
+        assert_eq!(
+            BaudRate::new(8_000_000, 1_000_000, OverSampling::Eight).map(|r| r.brr),
+            Ok(0x10)
+        );
+
+        // 整数部分超过 0xfff
+        assert_eq!(
+            BaudRate::new(24_000_000, 300, OverSampling::Sixteen),
+            Err(Error::BaudRate)
+        );
+        assert!(BaudRate::new(8_000_000, 300, OverSampling::Sixteen).is_ok());
+    }
+
+    #[test]
+    fn nearest_brr() {
+        // 能够实现时与 `BaudRate::new` 相同
+        for over_sampling in [OverSampling::Sixteen, OverSampling::Eight] {
+            let rate = BaudRate::new(8_000_000, 115_200, over_sampling).unwrap();
+            assert_eq!(
+                BaudRate::nearest_brr(8_000_000, 115_200, over_sampling),
+                rate.brr
+            );
+        }
+
+        // 超出范围时取最高或最低的波特率
+        assert_eq!(
+            BaudRate::nearest_brr(8_000_000, 1_000_000, OverSampling::Sixteen),
+            0x010
+        );
+        assert_eq!(
+            BaudRate::nearest_brr(24_000_000, 300, OverSampling::Sixteen),
+            0xffff
+        );
+        assert_eq!(
+            BaudRate::nearest_brr(24_000_000, 0, OverSampling::Eight),
+            0xfff7
+        );
+    }
+
+    #[test]
+    fn baud_rate_error() {
+        // 分频系数向上取整，实际波特率偏小
+        let rate = BaudRate::new(8_000_000, 57_600, OverSampling::Sixteen).unwrap();
+        assert_eq!(rate.actual, 57_553);
+        assert_eq!(rate.error_ppm, -815);
+        assert!(rate.within(815));
+        assert!(!rate.within(814));
+
+        let rate = BaudRate::new(8_000_000, 115_200, OverSampling::Sixteen).unwrap();
+        assert!(rate.within(BAUD_RATE_TOLERANCE_PPM));
+        assert!(!rate.within(5_000));
+
+        let rate = BaudRate::new(24_000_000, 9600, OverSampling::Sixteen).unwrap();
+        assert_eq!(rate.error_ppm, 0);
+        assert!(rate.within(0));
+    }
+}
@@ -0,0 +1,106 @@
+//! XMODEM/YMODEM 固件接收
+//!
+//! 通过串口接收固件，按 128 字节的页擦除并编程 flash，传输结束后回读整个写入区域校验 CRC。
+//! 支持 XMODEM-CRC（128 字节和 1K 数据块）和 YMODEM 批量传输的块 0。
+//!
+//! 协议状态机 [`Receiver`] 只通过 [`PageFlash`] 访问 flash，不依赖硬件，可以在主机上用内存
+//! 模拟的 flash 测试。[`receive`] 在任意 `embedded_io_async` 的读写对象（例如
+//! [`UsartRx`](crate::usart::UsartRx)/[`UsartTx`](crate::usart::UsartTx)）上驱动状态机，
+//! 处理超时、重发和取消。
+//!
+//! ```rust, ignore
+//! let flash = Flash::new(p.FLASH);
+//! let mut receiver = Receiver::new(flash, Protocol::Ymodem, 0x0800_8000, 32 * 1024)?;
+//! let (mut rx, mut tx) = usart.split();
+//! let len = xmodem::receive(&mut rx, &mut tx, &mut receiver).await?;
+//! ```
+
+mod receiver;
+mod types;
+
+pub use receiver::*;
+pub use types::*;
+
+#[cfg(feature = "embassy")]
+use embassy_time::{with_timeout, Duration};
+#[cfg(feature = "embassy")]
+use embedded_io_async::{Error as _, Read, ReadExactError, Write};
+
+/// 等待数据包第一个字节的时间
+#[cfg(feature = "embassy")]
+const PACKET_TIMEOUT: Duration = Duration::from_secs(3);
+/// 数据包中其余字节的时间
+#[cfg(feature = "embassy")]
+const BODY_TIMEOUT: Duration = Duration::from_secs(1);
+/// 出错后等待线路空闲的时间
+#[cfg(feature = "embassy")]
+const PURGE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// 接收一个文件，返回写入的字节数
+///
+/// 状态机返回错误时向发送方发送取消
+#[cfg(feature = "embassy")]
+pub async fn receive<R: Read, W: Write, F: PageFlash>(
+    rx: &mut R,
+    tx: &mut W,
+    receiver: &mut Receiver<F>,
+) -> Result<u32, Error> {
+    let mut buf = [0; MAX_PACKET_LEN];
+    let mut reply = receiver.start();
+
+    loop {
+        if reply == Reply::Nak {
+            purge(rx).await;
+        }
+        tx.write_all(reply.bytes())
+            .await
+            .map_err(|e| Error::Io(e.kind()))?;
+        if receiver.is_done() {
+            return Ok(receiver.written());
+        }
+
+        let rst = match read_packet(rx, &mut buf).await {
+            Some(len) => receiver.on_packet(&buf[..len]),
+            None => receiver.on_error(),
+        };
+
+        reply = match rst {
+            Ok(reply) => reply,
+            Err(e) => {
+                let _ = tx.write_all(Reply::Cancel.bytes()).await;
+                return Err(e);
+            }
+        };
+    }
+}
+
+/// 读取一个数据包，超时或出错时返回 `None`
+#[cfg(feature = "embassy")]
+async fn read_packet<R: Read>(rx: &mut R, buf: &mut [u8; MAX_PACKET_LEN]) -> Option<usize> {
+    with_timeout(PACKET_TIMEOUT, rx.read_exact(&mut buf[..1]))
+        .await
+        .ok()?
+        .ok()?;
+
+    let len = match buf[0] {
+        SOH => 3 + 128 + 2,
+        STX => MAX_PACKET_LEN,
+        // 取消时连续发送两个 CAN，和后面的一个字节一起交给状态机判断
+        CAN => 2,
+        _ => return Some(1),
+    };
+
+    let rst: Result<(), ReadExactError<R::Error>> =
+        with_timeout(BODY_TIMEOUT, rx.read_exact(&mut buf[1..len]))
+            .await
+            .ok()?;
+    rst.ok()?;
+    Some(len)
+}
+
+/// 丢弃数据直到线路空闲
+#[cfg(feature = "embassy")]
+async fn purge<R: Read>(rx: &mut R) {
+    let mut buf = [0; 16];
+    while let Ok(Ok(_)) = with_timeout(PURGE_TIMEOUT, rx.read(&mut buf)).await {}
+}
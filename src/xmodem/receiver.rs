@@ -0,0 +1,737 @@
+//! 接收状态机，只通过 [`PageFlash`] 访问 flash，不依赖硬件
+
+use super::*;
+use crate::flash::{self, Flash, FLASH_BASE_ADDR, FLASH_END_ADDR, FLASH_PAGE_SIZE};
+
+/// 开始传输前请求的最大次数，每次间隔一个数据包超时时间
+pub const START_RETRIES: u8 = 20;
+/// 连续错误的最大次数
+pub const MAX_RETRIES: u8 = 10;
+
+/// 按页擦除和编程的 flash
+pub trait PageFlash {
+    fn erase_page(&mut self, addr: u32) -> Result<(), flash::Error>;
+
+    fn program_page(&mut self, addr: u32, data: &[u8; FLASH_PAGE_SIZE])
+        -> Result<(), flash::Error>;
+
+    fn read_page(&mut self, addr: u32, buf: &mut [u8; FLASH_PAGE_SIZE])
+        -> Result<(), flash::Error>;
+}
+
+impl<'d, T: flash::Instance> PageFlash for Flash<'d, T> {
+    fn erase_page(&mut self, addr: u32) -> Result<(), flash::Error> {
+        Flash::erase_page(self, addr)
+    }
+
+    fn program_page(
+        &mut self,
+        addr: u32,
+        data: &[u8; FLASH_PAGE_SIZE],
+    ) -> Result<(), flash::Error> {
+        let mut content = [0; FLASH_PAGE_SIZE / 4];
+        for (word, v) in content.iter_mut().zip(data.chunks_exact(4)) {
+            *word = u32::from_le_bytes([v[0], v[1], v[2], v[3]]);
+        }
+        Flash::program_page(self, addr, content)
+    }
+
+    fn read_page(
+        &mut self,
+        addr: u32,
+        buf: &mut [u8; FLASH_PAGE_SIZE],
+    ) -> Result<(), flash::Error> {
+        let mut content = [0; FLASH_PAGE_SIZE / 4];
+        Flash::read_page(self, addr, &mut content)?;
+        for (v, word) in buf.chunks_exact_mut(4).zip(content) {
+            v.copy_from_slice(&word.to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+impl<F: PageFlash + ?Sized> PageFlash for &mut F {
+    fn erase_page(&mut self, addr: u32) -> Result<(), flash::Error> {
+        (**self).erase_page(addr)
+    }
+
+    fn program_page(
+        &mut self,
+        addr: u32,
+        data: &[u8; FLASH_PAGE_SIZE],
+    ) -> Result<(), flash::Error> {
+        (**self).program_page(addr, data)
+    }
+
+    fn read_page(
+        &mut self,
+        addr: u32,
+        buf: &mut [u8; FLASH_PAGE_SIZE],
+    ) -> Result<(), flash::Error> {
+        (**self).read_page(addr, buf)
+    }
+}
+
+/// CRC16-XMODEM，多项式 0x1021，`crc` 为之前的结果，从 0 开始
+pub fn crc16(crc: u16, data: &[u8]) -> u16 {
+    data.iter().fold(crc, |crc, v| {
+        let mut crc = crc ^ ((*v as u16) << 8);
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// YMODEM 等待块 0
+    Header,
+    /// 等待数据块或者 EOT
+    Data,
+    /// YMODEM 收到了第一个 EOT
+    Eot,
+    /// YMODEM 等待表示批量传输结束的空块 0
+    End,
+    Done,
+}
+
+/// 固件接收状态机
+///
+/// 由驱动循环调用：先发送 [`Receiver::start`] 的应答，之后每收到一个完整的数据包调用
+/// [`Receiver::on_packet`]，超时或者读取出错时调用 [`Receiver::on_error`]，把返回的应答发送给
+/// 发送方，直到 [`Receiver::is_done`]。返回错误时应当发送 [`Reply::Cancel`] 并结束传输
+pub struct Receiver<F: PageFlash> {
+    flash: F,
+    protocol: Protocol,
+    state: State,
+    /// 写入区域
+    start: u32,
+    capacity: u32,
+    /// 期望的下一个块号
+    block: u8,
+    /// 已经收到过数据包
+    started: bool,
+    errors: u8,
+    /// 连续收到的 CAN 的数量
+    cancels: usize,
+    /// 文件长度，XMODEM 没有
+    size: Option<u32>,
+    /// 已经接受的字节数
+    written: u32,
+    /// 已经接受的数据的 CRC
+    crc: u16,
+    /// 当前页的地址和数据
+    page_addr: u32,
+    page: [u8; FLASH_PAGE_SIZE],
+    page_len: usize,
+}
+
+impl<F: PageFlash> Receiver<F> {
+    /// 新建接收状态机，固件写入 `start` 开始的 `len` 个字节，两者都需要按页对齐
+    pub fn new(flash: F, protocol: Protocol, start: u32, len: u32) -> Result<Self, Error> {
+        let page = FLASH_PAGE_SIZE as u32;
+        if !start.is_multiple_of(page)
+            || !len.is_multiple_of(page)
+            || start < FLASH_BASE_ADDR
+            || start
+                .checked_add(len)
+                .is_none_or(|end| end > FLASH_END_ADDR)
+        {
+            return Err(Error::Region);
+        }
+
+        Ok(Self {
+            flash,
+            protocol,
+            state: match protocol {
+                Protocol::Xmodem => State::Data,
+                Protocol::Ymodem => State::Header,
+            },
+            start,
+            capacity: len,
+            block: match protocol {
+                Protocol::Xmodem => 1,
+                Protocol::Ymodem => 0,
+            },
+            started: false,
+            errors: 0,
+            cancels: 0,
+            size: None,
+            written: 0,
+            crc: 0,
+            page_addr: start,
+            page: [0xff; FLASH_PAGE_SIZE],
+            page_len: 0,
+        })
+    }
+
+    /// 开始传输时发送的应答
+    pub fn start(&self) -> Reply {
+        Reply::Request
+    }
+
+    /// 传输已经结束，写入的数据已经校验
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// 已经写入的字节数
+    pub fn written(&self) -> u32 {
+        self.written
+    }
+
+    /// YMODEM 块 0 中的文件长度
+    pub fn size(&self) -> Option<u32> {
+        self.size
+    }
+
+    /// 已经写入的数据的 CRC16-XMODEM
+    pub fn crc(&self) -> u16 {
+        self.crc
+    }
+
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// 等待数据包超时或者读取出错
+    pub fn on_error(&mut self) -> Result<Reply, Error> {
+        self.errors += 1;
+        let max = if self.started {
+            MAX_RETRIES
+        } else {
+            START_RETRIES
+        };
+        if self.errors > max {
+            return Err(Error::Retries);
+        }
+
+        // 还没有开始或者在等待下一个文件时重复请求，否则要求重发
+        Ok(if !self.started || self.state == State::End {
+            Reply::Request
+        } else {
+            Reply::Nak
+        })
+    }
+
+    /// 处理一个完整的数据包，EOT 是只有一个字节的数据包
+    ///
+    /// 连续两个 CAN 才取消传输，可以在一个数据包中，也可以分成两个数据包。
+    /// 单独的 CAN 可能是线路上的噪声，按错误的数据包处理
+    pub fn on_packet(&mut self, packet: &[u8]) -> Result<Reply, Error> {
+        if packet.first() == Some(&CAN) {
+            self.cancels = if packet.iter().all(|v| *v == CAN) {
+                self.cancels + packet.len()
+            } else {
+                0
+            };
+            if self.cancels >= 2 {
+                return Err(Error::Cancelled);
+            }
+            return self.on_error();
+        }
+        self.cancels = 0;
+
+        if packet == [EOT] {
+            return self.on_eot();
+        }
+
+        let Some(data) = Self::check_packet(packet) else {
+            return self.on_error();
+        };
+        let block = packet[1];
+        self.started = true;
+
+        self.errors = 0;
+
+        match self.state {
+            State::Header | State::End if block == 0 => self.on_header(data),
+            State::Data if block == self.block => {
+                self.block = self.block.wrapping_add(1);
+                self.on_data(data)?;
+                Ok(Reply::Ack)
+            }
+            // 发送方没有收到应答，重发了上一块，还没有数据时重发的是 YMODEM 的块 0
+            State::Data if block == self.block.wrapping_sub(1) => {
+                Ok(if self.protocol == Protocol::Ymodem && self.written == 0 {
+                    Reply::AckRequest
+                } else {
+                    Reply::Ack
+                })
+            }
+            _ => Err(Error::Sequence),
+        }
+    }
+
+    /// 检查数据包的长度、块号和 CRC，返回其中的数据
+    fn check_packet(packet: &[u8]) -> Option<&[u8]> {
+        let len = match packet.first() {
+            Some(&SOH) => 128,
+            Some(&STX) => 1024,
+            _ => return None,
+        };
+        if packet.len() != 3 + len + 2 || packet[1] != !packet[2] {
+            return None;
+        }
+
+        let data = &packet[3..3 + len];
+        let crc = u16::from_be_bytes([packet[3 + len], packet[4 + len]]);
+        (crc16(0, data) == crc).then_some(data)
+    }
+
+    /// YMODEM 块 0，文件名为空表示批量传输结束
+    fn on_header(&mut self, data: &[u8]) -> Result<Reply, Error> {
+        if data[0] == 0 {
+            if self.state == State::Header {
+                // 没有任何文件
+                self.finish()?;
+            }
+            self.state = State::Done;
+            return Ok(Reply::Ack);
+        }
+        if self.state == State::End {
+            // 只接收一个文件
+            return Err(Error::Overflow);
+        }
+
+        // 文件名之后是十进制的文件长度，以空格或 0 结束
+        let size = data
+            .iter()
+            .skip_while(|v| **v != 0)
+            .skip(1)
+            .take_while(|v| v.is_ascii_digit())
+            .try_fold(None, |size: Option<u32>, v| {
+                size.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|size| size.checked_add((v - b'0') as u32))
+                    .map(Some)
+            })
+            .ok_or(Error::Overflow)?;
+        if size.is_some_and(|size| size > self.capacity) {
+            return Err(Error::Overflow);
+        }
+
+        self.size = size;
+        self.state = State::Data;
+        self.block = 1;
+        Ok(Reply::AckRequest)
+    }
+
+    fn on_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        // 去掉文件长度之后的填充
+        let len = match self.size {
+            Some(size) => data.len().min((size - self.written) as usize),
+            None => data.len(),
+        };
+        let data = &data[..len];
+        if self.written as usize + len > self.capacity as usize {
+            return Err(Error::Overflow);
+        }
+
+        self.crc = crc16(self.crc, data);
+        self.written += len as u32;
+
+        for v in data {
+            self.page[self.page_len] = *v;
+            self.page_len += 1;
+            if self.page_len == FLASH_PAGE_SIZE {
+                self.program()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn on_eot(&mut self) -> Result<Reply, Error> {
+        match (self.protocol, self.state) {
+            (Protocol::Xmodem, State::Data) => {
+                self.finish()?;
+                self.state = State::Done;
+                Ok(Reply::Ack)
+            }
+            // YMODEM 的第一个 EOT 需要 NAK，确认发送方确实结束了
+            (Protocol::Ymodem, State::Data) => {
+                self.state = State::Eot;
+                Ok(Reply::Nak)
+            }
+            (Protocol::Ymodem, State::Eot) => {
+                self.finish()?;
+                self.state = State::End;
+                Ok(Reply::AckRequest)
+            }
+            // 重发的 EOT
+            (_, State::Done) => Ok(Reply::Ack),
+            (_, State::End) => Ok(Reply::AckRequest),
+            _ => Err(Error::Sequence),
+        }
+    }
+
+    /// 擦除并编程当前页，不足一页的部分填充 0xff
+    fn program(&mut self) -> Result<(), Error> {
+        self.page[self.page_len..].fill(0xff);
+        self.flash.erase_page(self.page_addr)?;
+        self.flash.program_page(self.page_addr, &self.page)?;
+        self.page_addr += FLASH_PAGE_SIZE as u32;
+        self.page_len = 0;
+        Ok(())
+    }
+
+    /// 写入最后不足一页的数据，回读校验整个写入的区域
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.page_len > 0 {
+            self.program()?;
+        }
+
+        let mut crc = 0;
+        let mut buf = [0; FLASH_PAGE_SIZE];
+        let mut addr = self.start;
+        let end = self.start + self.written;
+        while addr < end {
+            self.flash.read_page(addr, &mut buf)?;
+            let len = ((end - addr) as usize).min(FLASH_PAGE_SIZE);
+            crc = crc16(crc, &buf[..len]);
+            addr += FLASH_PAGE_SIZE as u32;
+        }
+
+        if crc != self.crc {
+            return Err(Error::Verify);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec;
+    use std::vec::Vec;
+
+    const START: u32 = FLASH_BASE_ADDR + 0x8000;
+
+    /// 内存模拟的 flash，`corrupt` 时回读的每一页第一个字节取反
+    struct MemFlash {
+        mem: Vec<u8>,
+        corrupt: bool,
+    }
+
+    impl MemFlash {
+        fn new(len: usize) -> Self {
+            Self {
+                mem: vec![0xff; len],
+                corrupt: false,
+            }
+        }
+
+        fn page(&mut self, addr: u32) -> &mut [u8] {
+            assert!(addr >= START && (addr - START).is_multiple_of(FLASH_PAGE_SIZE as u32));
+            let offset = (addr - START) as usize;
+            &mut self.mem[offset..offset + FLASH_PAGE_SIZE]
+        }
+    }
+
+    impl PageFlash for MemFlash {
+        fn erase_page(&mut self, addr: u32) -> Result<(), flash::Error> {
+            self.page(addr).fill(0xff);
+            Ok(())
+        }
+
+        fn program_page(
+            &mut self,
+            addr: u32,
+            data: &[u8; FLASH_PAGE_SIZE],
+        ) -> Result<(), flash::Error> {
+            self.page(addr).copy_from_slice(data);
+            Ok(())
+        }
+
+        fn read_page(
+            &mut self,
+            addr: u32,
+            buf: &mut [u8; FLASH_PAGE_SIZE],
+        ) -> Result<(), flash::Error> {
+            buf.copy_from_slice(self.page(addr));
+            if self.corrupt {
+                buf[0] = !buf[0];
+            }
+            Ok(())
+        }
+    }
+
+    /// 数据块，不足一块的部分填充 SUB，超过 128 字节时使用 1K 的块
+    fn packet(block: u8, data: &[u8]) -> Vec<u8> {
+        let (head, len) = if data.len() > 128 {
+            (STX, 1024)
+        } else {
+            (SOH, 128)
+        };
+        let mut packet = vec![head, block, !block];
+        packet.extend_from_slice(data);
+        packet.resize(3 + len, SUB);
+        let crc = crc16(0, &packet[3..]);
+        packet.extend_from_slice(&crc.to_be_bytes());
+        packet
+    }
+
+    /// YMODEM 块 0，`name` 为空时表示批量传输结束
+    fn header(name: &str, size: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        if !name.is_empty() {
+            data.extend_from_slice(name.as_bytes());
+            data.push(0);
+            data.extend_from_slice(std::format!("{} 0", size).as_bytes());
+        }
+        data.resize(128, 0);
+        packet(0, &data)
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7) as u8).collect()
+    }
+
+    fn receiver(protocol: Protocol, len: u32) -> Receiver<MemFlash> {
+        Receiver::new(MemFlash::new(len as usize), protocol, START, len).unwrap()
+    }
+
+    #[test]
+    fn region() {
+        let flash = || MemFlash::new(0);
+        assert!(matches!(
+            Receiver::new(flash(), Protocol::Xmodem, START + 1, 128),
+            Err(Error::Region)
+        ));
+        assert!(matches!(
+            Receiver::new(flash(), Protocol::Xmodem, START, 100),
+            Err(Error::Region)
+        ));
+        assert!(matches!(
+            Receiver::new(flash(), Protocol::Xmodem, FLASH_END_ADDR - 128, 256),
+            Err(Error::Region)
+        ));
+    }
+
+    #[test]
+    fn xmodem() {
+        let mut rx = receiver(Protocol::Xmodem, 2048);
+        let file = data(128 + 1024);
+
+        assert_eq!(rx.start(), Reply::Request);
+        assert_eq!(rx.on_packet(&packet(1, &file[..128])).unwrap(), Reply::Ack);
+        assert_eq!(rx.on_packet(&packet(2, &file[128..])).unwrap(), Reply::Ack);
+        assert!(!rx.is_done());
+        assert_eq!(rx.on_packet(&[EOT]).unwrap(), Reply::Ack);
+        assert!(rx.is_done());
+
+        assert_eq!(rx.written(), file.len() as u32);
+        assert_eq!(rx.crc(), crc16(0, &file));
+        // 重发的 EOT
+        assert_eq!(rx.on_packet(&[EOT]).unwrap(), Reply::Ack);
+
+        let flash = rx.into_inner();
+        assert_eq!(flash.mem[..file.len()], file);
+        assert!(flash.mem[file.len()..].iter().all(|v| *v == 0xff));
+    }
+
+    #[test]
+    fn xmodem_padding() {
+        // XMODEM 没有文件长度，最后一块的填充也会写入
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        rx.on_packet(&packet(1, &data(100))).unwrap();
+        rx.on_packet(&[EOT]).unwrap();
+        assert_eq!(rx.written(), 128);
+        assert!(rx.into_inner().mem[100..128].iter().all(|v| *v == SUB));
+    }
+
+    #[test]
+    fn ymodem() {
+        let mut rx = receiver(Protocol::Ymodem, 1024);
+        let file = data(200);
+
+        assert_eq!(rx.start(), Reply::Request);
+        assert_eq!(
+            rx.on_packet(&header("fw.bin", 200)).unwrap(),
+            Reply::AckRequest
+        );
+        assert_eq!(rx.size(), Some(200));
+        assert_eq!(rx.on_packet(&packet(1, &file[..128])).unwrap(), Reply::Ack);
+        assert_eq!(rx.on_packet(&packet(2, &file[128..])).unwrap(), Reply::Ack);
+
+        // 第一个 EOT 回复 NAK，第二个才结束文件
+        assert_eq!(rx.on_packet(&[EOT]).unwrap(), Reply::Nak);
+        assert_eq!(rx.on_packet(&[EOT]).unwrap(), Reply::AckRequest);
+        assert!(!rx.is_done());
+        // 等待下一个文件时超时继续请求
+        assert_eq!(rx.on_error().unwrap(), Reply::Request);
+        assert_eq!(rx.on_packet(&header("", 0)).unwrap(), Reply::Ack);
+        assert!(rx.is_done());
+
+        // 按文件长度截断最后一块的填充
+        assert_eq!(rx.written(), 200);
+        assert_eq!(rx.crc(), crc16(0, &file));
+        let flash = rx.into_inner();
+        assert_eq!(flash.mem[..200], file);
+        assert!(flash.mem[200..].iter().all(|v| *v == 0xff));
+    }
+
+    #[test]
+    fn ymodem_truncate_1k() {
+        let mut rx = receiver(Protocol::Ymodem, 2048);
+        let file = data(1030);
+        rx.on_packet(&header("fw.bin", 1030)).unwrap();
+        rx.on_packet(&packet(1, &file[..1024])).unwrap();
+        rx.on_packet(&packet(2, &file[1024..])).unwrap();
+        rx.on_packet(&[EOT]).unwrap();
+        rx.on_packet(&[EOT]).unwrap();
+
+        assert_eq!(rx.written(), 1030);
+        let flash = rx.into_inner();
+        assert_eq!(flash.mem[..1030], file);
+        assert_eq!(flash.mem[1030], 0xff);
+    }
+
+    #[test]
+    fn duplicate_block() {
+        let mut rx = receiver(Protocol::Ymodem, 1024);
+        let file = data(256);
+
+        rx.on_packet(&header("fw.bin", 256)).unwrap();
+        // 发送方没有收到块 0 的应答
+        assert_eq!(
+            rx.on_packet(&header("fw.bin", 256)).unwrap(),
+            Reply::AckRequest
+        );
+
+        assert_eq!(rx.on_packet(&packet(1, &file[..128])).unwrap(), Reply::Ack);
+        assert_eq!(rx.on_packet(&packet(1, &file[..128])).unwrap(), Reply::Ack);
+        assert_eq!(rx.written(), 128);
+
+        assert_eq!(rx.on_packet(&packet(2, &file[128..])).unwrap(), Reply::Ack);
+        assert_eq!(rx.written(), 256);
+
+        // 跳过了块号
+        assert!(matches!(
+            rx.on_packet(&packet(4, &file[..128])),
+            Err(Error::Sequence)
+        ));
+    }
+
+    #[test]
+    fn bad_crc() {
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        let file = data(128);
+
+        let mut bad = packet(1, &file);
+        bad[10] ^= 1;
+        // 还没有开始时继续请求
+        assert_eq!(rx.on_packet(&bad).unwrap(), Reply::Request);
+
+        rx.on_packet(&packet(1, &file)).unwrap();
+        let mut bad = packet(2, &file);
+        bad[3 + 128] ^= 1;
+        assert_eq!(rx.on_packet(&bad).unwrap(), Reply::Nak);
+        // 块号和反码不一致
+        let mut bad = packet(2, &file);
+        bad[2] = 0;
+        assert_eq!(rx.on_packet(&bad).unwrap(), Reply::Nak);
+
+        assert_eq!(rx.on_packet(&packet(2, &file)).unwrap(), Reply::Ack);
+        assert_eq!(rx.written(), 256);
+    }
+
+    #[test]
+    fn retries() {
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        for _ in 0..START_RETRIES {
+            assert_eq!(rx.on_error().unwrap(), Reply::Request);
+        }
+        assert!(matches!(rx.on_error(), Err(Error::Retries)));
+
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        rx.on_packet(&packet(1, &data(128))).unwrap();
+        let mut bad = packet(2, &data(128));
+        bad[3] ^= 1;
+        for _ in 0..MAX_RETRIES {
+            assert_eq!(rx.on_packet(&bad).unwrap(), Reply::Nak);
+        }
+        assert!(matches!(rx.on_packet(&bad), Err(Error::Retries)));
+
+        // 收到正确的数据包后重新计数
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        rx.on_packet(&packet(1, &data(128))).unwrap();
+        for block in 2..5 {
+            for _ in 0..MAX_RETRIES {
+                assert_eq!(rx.on_error().unwrap(), Reply::Nak);
+            }
+            assert_eq!(
+                rx.on_packet(&packet(block, &data(128))).unwrap(),
+                Reply::Ack
+            );
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        rx.on_packet(&packet(1, &data(128))).unwrap();
+
+        // 单独的 CAN 按错误的数据包处理
+        assert_eq!(rx.on_packet(&[CAN, 0x55]).unwrap(), Reply::Nak);
+        assert_eq!(rx.on_packet(&[CAN]).unwrap(), Reply::Nak);
+        assert_eq!(rx.on_packet(&packet(2, &data(128))).unwrap(), Reply::Ack);
+        assert!(matches!(rx.on_packet(&[CAN, CAN]), Err(Error::Cancelled)));
+
+        // 分成两个数据包的 CAN
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        assert_eq!(rx.on_packet(&[CAN]).unwrap(), Reply::Request);
+        assert!(matches!(rx.on_packet(&[CAN]), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut rx = receiver(Protocol::Xmodem, 256);
+        rx.on_packet(&packet(1, &data(128))).unwrap();
+        rx.on_packet(&packet(2, &data(128))).unwrap();
+        assert!(matches!(
+            rx.on_packet(&packet(3, &data(128))),
+            Err(Error::Overflow)
+        ));
+
+        let mut rx = receiver(Protocol::Xmodem, 512);
+        assert!(matches!(
+            rx.on_packet(&packet(1, &data(1024))),
+            Err(Error::Overflow)
+        ));
+
+        // 块 0 中的文件长度超过写入区域
+        let mut rx = receiver(Protocol::Ymodem, 256);
+        assert!(matches!(
+            rx.on_packet(&header("fw.bin", 257)),
+            Err(Error::Overflow)
+        ));
+
+        // 只接收一个文件
+        let mut rx = receiver(Protocol::Ymodem, 256);
+        rx.on_packet(&header("a.bin", 10)).unwrap();
+        rx.on_packet(&packet(1, &data(10))).unwrap();
+        rx.on_packet(&[EOT]).unwrap();
+        rx.on_packet(&[EOT]).unwrap();
+        assert!(matches!(
+            rx.on_packet(&header("b.bin", 10)),
+            Err(Error::Overflow)
+        ));
+    }
+
+    #[test]
+    fn verify() {
+        let mut rx = receiver(Protocol::Xmodem, 1024);
+        rx.flash.corrupt = true;
+        rx.on_packet(&packet(1, &data(128))).unwrap();
+        assert!(matches!(rx.on_packet(&[EOT]), Err(Error::Verify)));
+        assert!(!rx.is_done());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::flash;
+
+pub const SOH: u8 = 0x01;
+pub const STX: u8 = 0x02;
+pub const EOT: u8 = 0x04;
+pub const ACK: u8 = 0x06;
+pub const NAK: u8 = 0x15;
+pub const CAN: u8 = 0x18;
+/// 请求使用 CRC 校验
+pub const CRC_REQUEST: u8 = b'C';
+/// 最后一个数据块的填充
+pub const SUB: u8 = 0x1a;
+
+/// 最长的数据包：头、块号、块号反码、1024 字节数据和 2 字节 CRC
+pub const MAX_PACKET_LEN: usize = 3 + 1024 + 2;
+
+/// 协议
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// XMODEM-CRC，同时支持 128 字节和 1K 的数据块，
+    /// 没有文件长度，最后一块的填充也会写入 flash
+    Xmodem,
+    /// YMODEM 批量传输，只接收第一个文件，按块 0 中的文件长度截断填充
+    #[default]
+    Ymodem,
+}
+
+/// 接收方发送给发送方的应答
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reply {
+    /// 请求发送方开始传输（CRC 模式）
+    Request,
+    Ack,
+    Nak,
+    /// YMODEM 收到块 0 或者文件结束后，应答并请求下一步
+    AckRequest,
+    /// 取消传输
+    Cancel,
+}
+
+impl Reply {
+    pub fn bytes(&self) -> &'static [u8] {
+        match self {
+            Self::Request => &[CRC_REQUEST],
+            Self::Ack => &[ACK],
+            Self::Nak => &[NAK],
+            Self::AckRequest => &[ACK, CRC_REQUEST],
+            Self::Cancel => &[CAN, CAN],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// flash 擦除或编程错误
+    Flash(flash::Error),
+    /// 写入区域没有按页对齐或者超出了 flash
+    Region,
+    /// 文件超过了写入区域
+    Overflow,
+    /// 发送方取消了传输
+    Cancelled,
+    /// 超时或错误的数据包次数过多
+    Retries,
+    /// 块号不连续，无法恢复
+    Sequence,
+    /// 写入后回读的 CRC 与接收的数据不一致
+    Verify,
+    /// 串口读写错误
+    Io(embedded_io::ErrorKind),
+}
+
+impl From<flash::Error> for Error {
+    fn from(value: flash::Error) -> Self {
+        Self::Flash(value)
+    }
+}